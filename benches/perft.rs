@@ -0,0 +1,70 @@
+//! Times perft on the positions from `tests/perft.rs`, at the same depths,
+//! to see how changes to move generation affect its speed.
+//! Run with `cargo bench --bench perft`.
+
+use std::time::Instant;
+
+use justok::board::Board;
+use justok::perft::perft;
+
+const POSITIONS: [(&str, &str, u32); 5] = [
+    (
+        "standard",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        5,
+    ),
+    (
+        "kiwi pete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        4,
+    ),
+    (
+        "tricky",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        4,
+    ),
+    (
+        "position 4",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        4,
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5),
+];
+
+fn nps(nodes: u64, seconds: f64) -> u64 {
+    (nodes as f64 / seconds.max(1e-9)) as u64
+}
+
+fn main() {
+    println!();
+    println!(
+        "{:<12} {:>5} {:>10} {:>8} {:>12}",
+        "position", "depth", "nodes", "ms", "nps"
+    );
+    let mut total = 0;
+    let start = Instant::now();
+    for (name, fen, depth) in POSITIONS {
+        let board = Board::from_fen(fen);
+        let position_start = Instant::now();
+        let nodes = perft(&board, depth);
+        let elapsed = position_start.elapsed();
+        total += nodes;
+        println!(
+            "{:<12} {:>5} {:>10} {:>8} {:>12}",
+            name,
+            depth,
+            nodes,
+            elapsed.as_millis(),
+            nps(nodes, elapsed.as_secs_f64())
+        );
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<12} {:>5} {:>10} {:>8} {:>12}",
+        "total",
+        "",
+        total,
+        elapsed.as_millis(),
+        nps(total, elapsed.as_secs_f64())
+    );
+}
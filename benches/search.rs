@@ -0,0 +1,66 @@
+//! Searches a fixed set of positions to a fixed depth and reports the nodes searched,
+//! to see how changes to the search affect the size of the tree.
+//! Run with `cargo bench --bench search`; the node counts don't depend on the machine, the times do.
+
+use std::time::Instant;
+
+use justok::board::Board;
+use justok::game::Game;
+use justok::search::{search, Limits};
+
+const POSITIONS: [(&str, &str); 8] = [
+    (
+        "standard",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwi pete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "tricky",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ),
+    (
+        "position 4",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+    (
+        "italian",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 4 5",
+    ),
+    (
+        "queens gambit",
+        "rnbqkb1r/ppp2ppp/4pn2/3p4/2PP4/2N5/PP2PPPP/R1BQKBNR w KQkq - 2 4",
+    ),
+    ("rook endgame", "8/5pk1/6p1/R7/5P2/6PK/r7/8 b - - 0 40"),
+];
+
+/// Depth every position is searched to.
+const DEPTH: u32 = 7;
+
+fn main() {
+    let mut results = Vec::new();
+    let start = Instant::now();
+    for (name, fen) in POSITIONS {
+        let position_start = Instant::now();
+        let result = search(&Game::new(Board::from_fen(fen)), &Limits::to_depth(DEPTH));
+        results.push((name, result.nodes, position_start.elapsed()));
+    }
+    let elapsed = start.elapsed();
+
+    println!();
+    println!("depth {DEPTH}");
+    println!("{:<14} {:>12} {:>10}", "position", "nodes", "ms");
+    for (name, nodes, time) in &results {
+        println!("{:<14} {:>12} {:>10}", name, nodes, time.as_millis());
+    }
+    let total: u64 = results.iter().map(|(_, nodes, _)| nodes).sum();
+    println!("{:<14} {:>12} {:>10}", "total", total, elapsed.as_millis());
+    println!(
+        "{:<14} {:>12}",
+        "nps",
+        (total as f64 / elapsed.as_secs_f64()) as u64
+    );
+}
@@ -0,0 +1,130 @@
+//! Compares walking rays square by square with magic bitboard lookups for sliding attacks,
+//! using the sliders found in the perft positions from `tests/perft.rs`.
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use justok::bitboard::{
+    bishop_attacks, rook_attacks, sliding_attacks, squares, Bitboard, BISHOP_DIRECTIONS,
+    ROOK_DIRECTIONS,
+};
+use justok::board::Board;
+use justok::{Color, Square};
+
+const POSITIONS: [(&str, &str); 5] = [
+    (
+        "standard",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwi pete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "tricky",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ),
+    (
+        "position 4",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+/// How many times every lookup is repeated, to get measurable timings.
+const REPETITIONS: usize = 20;
+
+/// A slider on a square along with the occupancy of the board it stands on.
+struct Lookup {
+    square: Square,
+    occupied: Bitboard,
+    is_rook: bool,
+}
+
+/// Collect every rook and bishop lookup (queens count as both) in the tree below a board.
+fn collect_lookups(board: Board, depth: usize, lookups: &mut Vec<Lookup>) {
+    for color in [Color::White, Color::Black] {
+        let [_, _, bishop, rook, queen, _] = color.pieces();
+        for (is_rook, bb) in [
+            (false, board.bitboard(bishop) | board.bitboard(queen)),
+            (true, board.bitboard(rook) | board.bitboard(queen)),
+        ] {
+            for square in squares(bb) {
+                lookups.push(Lookup {
+                    square,
+                    occupied: board.occupied(),
+                    is_rook,
+                });
+            }
+        }
+    }
+    if depth == 0 {
+        return;
+    }
+    for m in board.generate_moves() {
+        let mut next = board;
+        next.apply(m);
+        collect_lookups(next, depth - 1, lookups);
+    }
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..REPETITIONS {
+        f();
+    }
+    start.elapsed()
+}
+
+fn per_lookup(duration: Duration, lookups: usize) -> f64 {
+    duration.as_nanos() as f64 / (lookups * REPETITIONS) as f64
+}
+
+fn main() {
+    // Build the magic tables up front so their construction isn't timed.
+    black_box(rook_attacks(0, 0));
+
+    println!(
+        "{:<12} {:>9} {:>12} {:>12} {:>8}",
+        "position", "lookups", "rays ns/op", "magic ns/op", "speedup"
+    );
+    for (name, fen) in POSITIONS {
+        let mut lookups = Vec::new();
+        collect_lookups(Board::from_fen(fen), 2, &mut lookups);
+
+        let rays = time(|| {
+            for l in &lookups {
+                let directions = if l.is_rook {
+                    &ROOK_DIRECTIONS
+                } else {
+                    &BISHOP_DIRECTIONS
+                };
+                black_box(sliding_attacks(
+                    black_box(l.square),
+                    black_box(l.occupied),
+                    directions,
+                ));
+            }
+        });
+        let magic = time(|| {
+            for l in &lookups {
+                let attacks = if l.is_rook {
+                    rook_attacks(black_box(l.square), black_box(l.occupied))
+                } else {
+                    bishop_attacks(black_box(l.square), black_box(l.occupied))
+                };
+                black_box(attacks);
+            }
+        });
+
+        println!(
+            "{:<12} {:>9} {:>12.2} {:>12.2} {:>7.1}x",
+            name,
+            lookups.len(),
+            per_lookup(rays, lookups.len()),
+            per_lookup(magic, lookups.len()),
+            rays.as_secs_f64() / magic.as_secs_f64()
+        );
+    }
+}
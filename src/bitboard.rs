@@ -0,0 +1,146 @@
+use crate::{magic, Square};
+
+/// A set of squares stored as one bit per square.
+/// Bit 0 is a1, bit 7 is h1 and bit 63 is h8.
+pub type Bitboard = u64;
+
+/// The light squares of the board, like b1 and a2.
+pub const LIGHT_SQUARES: Bitboard = 0x55AA_55AA_55AA_55AA;
+
+/// A bitboard with only a single square set.
+pub const fn square_bb(square: Square) -> Bitboard {
+    1 << square
+}
+
+/// Iterate over the squares set in a bitboard, going from a1 towards h8.
+pub fn squares(bb: Bitboard) -> Squares {
+    Squares(bb)
+}
+
+/// Iterator over the squares in a [Bitboard], see [squares].
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as Square;
+        // Clear the lowest set bit.
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+/// Compute the squares reachable by a single jump with each of the (rank, file) offsets from every square.
+const fn leaper_attacks(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let rank = square as i8 / 8;
+        let file = square as i8 % 8;
+        let mut i = 0;
+        while i < offsets.len() {
+            let (r, f) = (rank + offsets[i].0, file + offsets[i].1);
+            if r >= 0 && r < 8 && f >= 0 && f < 8 {
+                table[square] |= square_bb((r * 8 + f) as Square);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+/// Squares a knight attacks from each square.
+/// Knights may move two squares orthogonally and then one square along the other orthogonal axis.
+/// . x . x .
+/// x . . . x
+/// . . n . .
+/// x . . . x
+/// . x . x .
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&[
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+]);
+
+/// Squares a king attacks from each square, which is any surrounding square.
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&[
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, 1),
+    (0, -1),
+]);
+
+/// Squares a pawn attacks from each square, indexed by [crate::Color] and then square.
+/// Pawns capture diagonally towards the opposing player.
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[(1, 1), (1, -1)]),
+    leaper_attacks(&[(-1, 1), (-1, -1)]),
+];
+
+pub const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, -1), (-1, 1), (1, -1)];
+
+/// Squares attacked by sliding from a square in each of the (rank, file) directions.
+/// Each ray stops at the first occupied square, which is included since it may be captured.
+/// This walks each ray square by square, prefer [rook_attacks] and [bishop_attacks] which use lookup tables.
+pub fn sliding_attacks(square: Square, occupied: Bitboard, directions: &[(i8, i8)]) -> Bitboard {
+    let mut attacks = 0;
+    let rank = square as i8 / 8;
+    let file = square as i8 % 8;
+    for (ro, fo) in directions {
+        let mut r = rank;
+        let mut f = file;
+        loop {
+            r += ro;
+            f += fo;
+            if r > 7 || f > 7 || r < 0 || f < 0 {
+                break;
+            }
+            let bb = square_bb((r * 8 + f) as Square);
+            attacks |= bb;
+            if occupied & bb != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+/// Squares attacked by a rook on a square given the occupied squares of the board.
+/// Same result as [sliding_attacks] with [ROOK_DIRECTIONS], but looked up in a precomputed table.
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    magic::rook_attacks(square, occupied)
+}
+
+/// Squares attacked by a bishop on a square given the occupied squares of the board.
+/// Same result as [sliding_attacks] with [BISHOP_DIRECTIONS], but looked up in a precomputed table.
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    magic::bishop_attacks(square, occupied)
+}
+
+/// Squares strictly between two squares on the same rank, file or diagonal.
+/// Empty if the squares don't share a line, or are next to each other.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    // Where the rays from both squares meet, with the other square blocking each ray.
+    if rook_attacks(a, 0) & square_bb(b) != 0 {
+        rook_attacks(a, square_bb(b)) & rook_attacks(b, square_bb(a))
+    } else if bishop_attacks(a, 0) & square_bb(b) != 0 {
+        bishop_attacks(a, square_bb(b)) & bishop_attacks(b, square_bb(a))
+    } else {
+        0
+    }
+}
@@ -1,10 +1,167 @@
 use std::fmt::Display;
 
-use crate::{square_from_algebraic, square_to_algebraic, to_board_square, Move, Piece, Square};
+use crate::{
+    bitboard::{
+        between, bishop_attacks, rook_attacks, square_bb, squares, Bitboard, KING_ATTACKS,
+        KNIGHT_ATTACKS, LIGHT_SQUARES, PAWN_ATTACKS,
+    },
+    eval::piece_value,
+    square_to_algebraic, try_square_from_algebraic, zobrist, Color, Move, MoveList, Piece, Square,
+};
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+/// Reasons a Forsyth-Edwards-Notation (FEN) string could not be read into a [Board].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenError {
+    /// The string did not have between 4 and 6 whitespace separated fields.
+    FieldCount(usize),
+    /// Piece placement (field 1) did not have exactly 8 ranks separated by '/'.
+    RankCount(usize),
+    /// Piece placement (field 1) had a rank that does not cover exactly 8 squares.
+    RankLength { rank: u8, squares: u32 },
+    /// Piece placement (field 1) contained a character that is neither a piece nor a digit 1-8.
+    UnknownPiece(char),
+    /// Side to move (field 2) was not 'w' or 'b'.
+    SideToMove(String),
+    /// Castling ability (field 3) was not '-' or an ordered subset of "KQkq".
+    Castling(String),
+    /// En pessant target square (field 4) was not '-' or a square on the 3rd or 6th rank.
+    EnPessant(String),
+    /// Halfmove clock (field 5) was not a non-negative number.
+    HalfMoveClock(String),
+    /// Fullmove counter (field 6) was not a non-negative number.
+    FullMoveCounter(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "expected 4 to 6 fields, found {n}"),
+            FenError::RankCount(n) => write!(f, "piece placement: expected 8 ranks, found {n}"),
+            FenError::RankLength { rank, squares } => write!(
+                f,
+                "piece placement: rank {rank} covers {squares} squares, expected 8"
+            ),
+            FenError::UnknownPiece(c) => write!(f, "piece placement: unknown piece '{c}'"),
+            FenError::SideToMove(s) => write!(f, "side to move: expected 'w' or 'b', found '{s}'"),
+            FenError::Castling(s) => write!(f, "castling ability: malformed '{s}'"),
+            FenError::EnPessant(s) => write!(f, "en pessant square: malformed '{s}'"),
+            FenError::HalfMoveClock(s) => write!(f, "halfmove clock: not a number '{s}'"),
+            FenError::FullMoveCounter(s) => write!(f, "fullmove counter: not a number '{s}'"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// The state lost when making a move, which is needed to take the move back.
+/// See [Board::make_move] and [Board::unmake_move].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UndoInfo {
+    /// The captured piece and the square it was captured on, which differ for en pessant.
+    captured: Option<(Piece, Square)>,
+    en_pessant_square: Option<Square>,
+    /// Castling rights ordered white king side, white queen side, black king side, black queen side.
+    castling_rights: [bool; 4],
+    half_move_clock: u32,
+    full_move_counter: u32,
+    hash: u64,
+}
+
+/// Which kind of moves to generate, see [Board::generate].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GenType {
+    /// Every move.
+    All,
+    /// Moves capturing a piece, including en pessant, and promotions to a queen.
+    Captures,
+    /// Every move that isn't one of the [GenType::Captures].
+    Quiets,
+    /// Moves that may get the king out of check. The same as [GenType::All] when not in check.
+    Evasions,
+}
+
+/// The ways a game of chess can end, see [Board::outcome].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Outcome {
+    /// The side to move is in check and has no legal moves. Holds the winning side.
+    Checkmate(Color),
+    /// The side to move is not in check but has no legal moves.
+    Stalemate,
+    /// Neither side has enough material left to checkmate.
+    InsufficientMaterial,
+    /// 50 moves by each side have passed without a capture or pawn move.
+    FiftyMoveRule,
+    /// The same position has occurred three times. Requires the move history, see [crate::game::Game::outcome].
+    ThreefoldRepetition,
+}
+
+/// A way in which a [Board] breaks the rules of chess, see [Board::validate].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Violation {
+    /// A side does not have exactly one king.
+    KingCount { color: Color, count: u32 },
+    /// A pawn stands on the first or eighth rank.
+    PawnOnBackRank { square: Square },
+    /// The side that is not to move is in check, so its king could be captured.
+    OpponentInCheck,
+    /// A side has a castling right, but its king or that rook has left its starting square.
+    CastlingRights { color: Color, king_side: bool },
+    /// The en pessant square could not have been created by the last move being a double pawn push.
+    EnPessant { square: Square },
+    /// A side has more than 16 pieces.
+    TooManyPieces { color: Color, count: u32 },
+    /// A side has more than 8 pawns.
+    TooManyPawns { color: Color, count: u32 },
+    /// A side has more pieces beyond the starting set than it could have promoted from missing pawns.
+    TooManyPromotions { color: Color },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::KingCount { color, count } => {
+                write!(f, "{color} has {count} kings, expected 1")
+            }
+            Violation::PawnOnBackRank { square } => {
+                write!(f, "pawn on back rank at {}", square_to_algebraic(*square))
+            }
+            Violation::OpponentInCheck => write!(f, "side not to move is in check"),
+            Violation::CastlingRights { color, king_side } => write!(
+                f,
+                "{color} may castle {} but king or rook has moved",
+                if *king_side {
+                    "king side"
+                } else {
+                    "queen side"
+                }
+            ),
+            Violation::EnPessant { square } => write!(
+                f,
+                "en pessant square {} is impossible",
+                square_to_algebraic(*square)
+            ),
+            Violation::TooManyPieces { color, count } => {
+                write!(f, "{color} has {count} pieces, at most 16 allowed")
+            }
+            Violation::TooManyPawns { color, count } => {
+                write!(f, "{color} has {count} pawns, at most 8 allowed")
+            }
+            Violation::TooManyPromotions { color } => {
+                write!(f, "{color} has more promoted pieces than missing pawns")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Board {
-    pieces: [Option<Piece>; 64],
+    /// One bitboard per [Piece], indexed by the value of the piece.
+    /// Index 0 and 7 do not correspond to a piece and are always empty.
+    pieces: [Bitboard; 14],
+    /// All squares occupied by each side, indexed by [Color].
+    occupancy: [Bitboard; 2],
+    /// The piece on each square, so [Board::at] doesn't have to search the bitboards.
+    mailbox: [Option<Piece>; 64],
     white_to_move: bool,
     en_pessant_square: Option<Square>,
     can_white_castle_king_side: bool,
@@ -13,13 +170,17 @@ pub struct Board {
     can_black_castle_queen_side: bool,
     half_move_clock: u32,
     full_move_counter: u32,
+    /// Zobrist hash of the position, kept up to date as pieces and rights change.
+    hash: u64,
 }
 
 impl Board {
     /// Create a new chess board with no pieces placed.
     pub fn blank() -> Self {
-        Self {
-            pieces: [None; 64],
+        let mut board = Self {
+            pieces: [0; 14],
+            occupancy: [0; 2],
+            mailbox: [None; 64],
             white_to_move: true,
             en_pessant_square: None,
             can_white_castle_king_side: true,
@@ -28,13 +189,24 @@ impl Board {
             can_black_castle_queen_side: true,
             half_move_clock: 0,
             full_move_counter: 0,
-        }
+            hash: 0,
+        };
+        board.hash = board.compute_hash();
+        board
     }
 
     /// Create a board from a Forsyth-Edwards-Notation (FEN) string.
+    /// Will panic if the string is not valid FEN, see [Board::try_from_fen] for a fallible version.
     pub fn from_fen(fen: &str) -> Self {
-        // TODO: Make this return Result and don't panic
+        match Self::try_from_fen(fen) {
+            Ok(board) => board,
+            Err(e) => panic!("Not a valid FEN string: '{fen}': {e}"),
+        }
+    }
 
+    /// Create a board from a Forsyth-Edwards-Notation (FEN) string.
+    /// Returns a [FenError] describing the first problem found if the string is not valid FEN.
+    pub fn try_from_fen(fen: &str) -> Result<Self, FenError> {
         // FEN contains 6 fields separated by space.
         // They are:
         // 1. Piece placement.
@@ -46,7 +218,7 @@ impl Board {
         // Fields 5. and 6. may be left out.
         let fields: Vec<_> = fen.split_whitespace().collect();
         if fields.len() > 6 || fields.len() < 4 {
-            panic!("Not a valid FEN string: '{fen}'");
+            return Err(FenError::FieldCount(fields.len()));
         }
 
         // Read piece placement and place onto blank board.
@@ -57,24 +229,36 @@ impl Board {
         // rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR
         let mut board = Self::blank();
         let placement = fields[0];
-        for (rank_idx, rank_str) in placement.split('/').enumerate() {
+        let ranks: Vec<_> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        for (rank_idx, rank_str) in ranks.into_iter().enumerate() {
             let rank: u8 = 7 - (rank_idx as u8);
-            let mut file: u8 = 0;
+            let mut file: u32 = 0;
             for piece in rank_str.chars() {
                 match piece {
-                    '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' => {
+                    '1'..='8' => {
                         // Skip this amount of squares
-                        file += piece.to_string().parse::<u8>().unwrap();
+                        file += piece.to_digit(10).unwrap();
                     }
                     p => {
-                        if let Some(valid) = Piece::from_char(&p) {
-                            board.place(valid, rank * 8 + file);
-                            file += 1
-                        } else {
-                            panic!("Not a valid FEN string: '{fen}'")
+                        let valid = Piece::from_char(&p).ok_or(FenError::UnknownPiece(p))?;
+                        if file < 8 {
+                            board.place(valid, rank * 8 + file as u8);
                         }
+                        file += 1
                     }
                 }
+                if file > 8 {
+                    break;
+                }
+            }
+            if file != 8 {
+                return Err(FenError::RankLength {
+                    rank: rank + 1,
+                    squares: file,
+                });
             }
         }
 
@@ -82,29 +266,52 @@ impl Board {
         match fields[1] {
             "w" => board.white_to_move = true,
             "b" => board.white_to_move = false,
-            _ => panic!("Not a valid FEN string: '{fen}'"),
+            other => return Err(FenError::SideToMove(other.to_owned())),
         }
 
         // Read castling rights.
+        // Either '-' or a subset of "KQkq" in that order.
         let castling = fields[2];
-        board.can_white_castle_king_side = castling.contains("K");
-        board.can_white_castle_queen_side = castling.contains("Q");
-        board.can_black_castle_king_side = castling.contains("k");
-        board.can_black_castle_queen_side = castling.contains("q");
+        let is_ordered_subset = |s: &str| {
+            let mut rest = "KQkq".chars();
+            !s.is_empty() && s.chars().all(|c| rest.any(|r| r == c))
+        };
+        if castling != "-" && !is_ordered_subset(castling) {
+            return Err(FenError::Castling(castling.to_owned()));
+        }
+        board.can_white_castle_king_side = castling.contains('K');
+        board.can_white_castle_queen_side = castling.contains('Q');
+        board.can_black_castle_king_side = castling.contains('k');
+        board.can_black_castle_queen_side = castling.contains('q');
 
-        // Read en pessant square.
+        // Read en pessant square. It can only ever be on the 3rd or 6th rank.
         match fields[3] {
             "-" => {
                 board.en_pessant_square = None;
             }
-            square => board.en_pessant_square = Some(square_from_algebraic(square)),
+            square => match try_square_from_algebraic(square) {
+                Some(s) if s / 8 == 2 || s / 8 == 5 => board.en_pessant_square = Some(s),
+                _ => return Err(FenError::EnPessant(square.to_owned())),
+            },
         }
 
         // Read half and full clock counts.
-        board.half_move_clock = fields[4].parse::<u32>().unwrap();
-        board.full_move_counter = fields[5].parse::<u32>().unwrap();
+        // When left out they default to the values of a fresh game.
+        board.half_move_clock = match fields.get(4) {
+            None => 0,
+            Some(clock) => clock
+                .parse::<u32>()
+                .map_err(|_| FenError::HalfMoveClock((*clock).to_owned()))?,
+        };
+        board.full_move_counter = match fields.get(5) {
+            None => 1,
+            Some(counter) => counter
+                .parse::<u32>()
+                .map_err(|_| FenError::FullMoveCounter((*counter).to_owned()))?,
+        };
 
-        board
+        board.hash = board.compute_hash();
+        Ok(board)
     }
 
     /// Create a Forsyth-Edwards-Notation (FEN) string from the current board.
@@ -190,470 +397,874 @@ impl Board {
         fen
     }
 
-    /// Place a [Piece] within the board without updating any other state.
+    /// Place a [Piece] within the board without updating any other state than the hash.
+    /// Any piece already on the square is removed.
     fn place(&mut self, piece: Piece, at: Square) {
-        self.pieces[at as usize] = Some(piece);
+        self.remove(at);
+        self.hash ^= zobrist::PIECE_SQUARE[piece as usize * 64 + at as usize];
+        let bb = square_bb(at);
+        self.pieces[piece as usize] |= bb;
+        self.occupancy[piece.color() as usize] |= bb;
+        self.mailbox[at as usize] = Some(piece);
+    }
+
+    /// Remove the [Piece] on a square without updating any other state than the hash.
+    fn remove(&mut self, at: Square) -> Option<Piece> {
+        let piece = self.mailbox[at as usize].take()?;
+        self.hash ^= zobrist::PIECE_SQUARE[piece as usize * 64 + at as usize];
+        let bb = square_bb(at);
+        self.pieces[piece as usize] &= !bb;
+        self.occupancy[piece.color() as usize] &= !bb;
+        Some(piece)
     }
 
     /// Applies a move to the board. The move is assummed to be legal.
     pub fn apply(&mut self, r#move: Move) {
-        let is_capture = self.at(r#move.to).is_some();
-        if let Some(p) = self.at(r#move.from) {
-            self.pieces[r#move.from as usize] = None;
-            self.pieces[r#move.to as usize] = r#move.promote_to.or(Some(p));
-
-            // Set the half clock.
-            let is_pawn_move = p == Piece::PawnWhite || p == Piece::PawnBlack;
-            if is_pawn_move || is_capture {
-                self.half_move_clock = 0;
-            } else {
-                self.half_move_clock += 1;
+        self.make_move(r#move);
+    }
+
+    /// Applies a move to the board. The move is assummed to be legal.
+    /// Returns what is needed to take the move back again with [Board::unmake_move].
+    pub fn make_move(&mut self, r#move: Move) -> UndoInfo {
+        let mut undo = UndoInfo {
+            captured: None,
+            en_pessant_square: self.en_pessant_square,
+            castling_rights: [
+                self.can_white_castle_king_side,
+                self.can_white_castle_queen_side,
+                self.can_black_castle_king_side,
+                self.can_black_castle_queen_side,
+            ],
+            half_move_clock: self.half_move_clock,
+            full_move_counter: self.full_move_counter,
+            hash: self.hash,
+        };
+
+        // Rights are hashed from scratch after the move, so drop the current ones from the hash.
+        let rights_hash = self.rights_hash();
+        let Some(p) = self.remove(r#move.from) else {
+            return undo;
+        };
+        self.hash ^= rights_hash;
+        undo.captured = self.remove(r#move.to).map(|c| (c, r#move.to));
+        self.place(r#move.promote_to.unwrap_or(p), r#move.to);
+
+        // Set the half clock.
+        let is_pawn_move = p == Piece::PawnWhite || p == Piece::PawnBlack;
+        if is_pawn_move || undo.captured.is_some() {
+            self.half_move_clock = 0;
+        } else {
+            self.half_move_clock += 1;
+        }
+
+        // Capture en-pessant
+        let changed_file = (r#move.from % 8) != (r#move.to % 8);
+        if is_pawn_move && changed_file && undo.captured.is_none() {
+            // A pawn changed file without doing a capture. This only happens by en pessant.
+            let captured_pawn_square = match self.white_to_move {
+                true => r#move.to - 8,
+                false => r#move.to + 8,
+            };
+            undo.captured = self
+                .remove(captured_pawn_square)
+                .map(|c| (c, captured_pawn_square));
+        }
+
+        // Set en pessant square.
+        if is_pawn_move {
+            self.en_pessant_square = match r#move.to as i8 - r#move.from as i8 {
+                16 => Some(r#move.from + 8),
+                -16 => Some(r#move.from - 8),
+                _ => None,
             }
+        } else {
+            self.en_pessant_square = None;
+        }
 
-            // Capture en-pessant
-            let changed_file = (r#move.from % 8) != (r#move.to % 8);
-            if is_pawn_move && changed_file && !is_capture {
-                // A pawn changed file without doing a capture. This only happens by en pessant.
-                let captured_pawn_square = match self.white_to_move {
-                    true => r#move.to - 8,
-                    false => r#move.to + 8,
-                };
-                self.pieces[captured_pawn_square as usize] = None;
+        // Move the rook when a player castles.
+        if let Some((rook, rook_from, rook_to)) = castling_rook_move(p, r#move) {
+            self.remove(rook_from);
+            self.place(rook, rook_to);
+        }
+
+        // Update castling rights
+        self.can_white_castle_queen_side &=
+            !(p == Piece::KingWhite || r#move.from == 0 || r#move.to == 0);
+        self.can_white_castle_king_side &=
+            !(p == Piece::KingWhite || r#move.from == 7 || r#move.to == 7);
+        self.can_black_castle_queen_side &=
+            !(p == Piece::KingBlack || r#move.from == 56 || r#move.to == 56);
+        self.can_black_castle_king_side &=
+            !(p == Piece::KingBlack || r#move.from == 63 || r#move.to == 63);
+
+        // Update whose turn it is, and increment the move counter if needed.
+        self.white_to_move = !self.white_to_move;
+        if self.white_to_move {
+            self.full_move_counter += 1;
+        }
+        self.hash ^= zobrist::BLACK_TO_MOVE;
+        self.hash ^= self.rights_hash();
+
+        undo
+    }
+
+    /// Takes back a move made with [Board::make_move], restoring the board exactly as it was.
+    /// Moves must be taken back in the reverse order they were made.
+    pub fn unmake_move(&mut self, r#move: Move, undo: UndoInfo) {
+        let Some(moved) = self.remove(r#move.to) else {
+            return;
+        };
+        self.white_to_move = !self.white_to_move;
+
+        // A promoted piece goes back to being a pawn.
+        let p = match r#move.promote_to {
+            Some(_) => self.side_to_move().pieces()[0],
+            None => moved,
+        };
+        self.place(p, r#move.from);
+        if let Some((captured, square)) = undo.captured {
+            self.place(captured, square);
+        }
+
+        // Move the rook back when the move was castling.
+        if let Some((rook, rook_from, rook_to)) = castling_rook_move(p, r#move) {
+            self.remove(rook_to);
+            self.place(rook, rook_from);
+        }
+
+        self.en_pessant_square = undo.en_pessant_square;
+        [
+            self.can_white_castle_king_side,
+            self.can_white_castle_queen_side,
+            self.can_black_castle_king_side,
+            self.can_black_castle_queen_side,
+        ] = undo.castling_rights;
+        self.half_move_clock = undo.half_move_clock;
+        self.full_move_counter = undo.full_move_counter;
+        self.hash = undo.hash;
+    }
+
+    /// Pass the turn to the other side without moving, which is only legal when not in check.
+    /// The half move clock is reset, so repetitions are not looked for across the null move.
+    /// Returns what is needed to take it back with [Board::unmake_null_move].
+    pub fn make_null_move(&mut self) -> UndoInfo {
+        let undo = UndoInfo {
+            captured: None,
+            en_pessant_square: self.en_pessant_square,
+            castling_rights: [
+                self.can_white_castle_king_side,
+                self.can_white_castle_queen_side,
+                self.can_black_castle_king_side,
+                self.can_black_castle_queen_side,
+            ],
+            half_move_clock: self.half_move_clock,
+            full_move_counter: self.full_move_counter,
+            hash: self.hash,
+        };
+        self.hash ^= self.rights_hash();
+        self.en_pessant_square = None;
+        self.half_move_clock = 0;
+        self.white_to_move = !self.white_to_move;
+        if self.white_to_move {
+            self.full_move_counter += 1;
+        }
+        self.hash ^= zobrist::BLACK_TO_MOVE;
+        self.hash ^= self.rights_hash();
+        undo
+    }
+
+    /// Takes back a null move made with [Board::make_null_move].
+    pub fn unmake_null_move(&mut self, undo: UndoInfo) {
+        self.white_to_move = !self.white_to_move;
+        self.en_pessant_square = undo.en_pessant_square;
+        self.half_move_clock = undo.half_move_clock;
+        self.full_move_counter = undo.full_move_counter;
+        self.hash = undo.hash;
+    }
+
+    /// The Zobrist hash of the position.
+    /// Covers the pieces, side to move, castling rights and en pessant file, but not the clocks.
+    /// Two boards with the same hash are almost certainly the same position.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Compute the Zobrist hash of the position from scratch.
+    fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square in 0..64 {
+            if let Some(piece) = self.at(square) {
+                hash ^= zobrist::PIECE_SQUARE[piece as usize * 64 + square as usize];
+            }
+        }
+        if !self.white_to_move {
+            hash ^= zobrist::BLACK_TO_MOVE;
+        }
+        hash ^ self.rights_hash()
+    }
+
+    /// The part of the Zobrist hash covering castling rights and the en pessant file.
+    /// The en pessant file is only included when a pawn can actually capture en pessant,
+    /// so positions that only differ by an unusable en pessant square count as repetitions.
+    fn rights_hash(&self) -> u64 {
+        let mut hash = 0;
+        let rights = [
+            self.can_white_castle_king_side,
+            self.can_white_castle_queen_side,
+            self.can_black_castle_king_side,
+            self.can_black_castle_queen_side,
+        ];
+        for (key, has_right) in zobrist::CASTLING.iter().zip(rights) {
+            if has_right {
+                hash ^= key;
+            }
+        }
+        if let Some(square) = self.en_pessant_square {
+            let us = self.side_to_move();
+            let capturers = PAWN_ATTACKS[us.opposite() as usize][square as usize]
+                & self.bitboard(us.pieces()[0]);
+            if capturers != 0 {
+                hash ^= zobrist::EN_PESSANT_FILE[square as usize % 8];
             }
+        }
+        hash
+    }
 
-            // Set en pessant square.
-            if is_pawn_move {
-                self.en_pessant_square = match r#move.to as i8 - r#move.from as i8 {
-                    16 => Some(r#move.from + 8),
-                    -16 => Some(r#move.from - 8),
-                    _ => None,
-                }
-            } else {
-                self.en_pessant_square = None;
+    /// Check that the board describes a position that could occur in a game of chess.
+    /// Returns every [Violation] found, so an empty list means the position is legal.
+    pub fn validate(&self) -> Vec<Violation> {
+        const COLORS: [Color; 2] = [Color::White, Color::Black];
+        let mut violations = Vec::new();
+
+        let count = |piece: Piece| (0..64).filter(|&s| self.at(s) == Some(piece)).count() as u32;
+
+        // Each side must have exactly one king.
+        for color in COLORS {
+            let [.., king] = color.pieces();
+            let kings = count(king);
+            if kings != 1 {
+                violations.push(Violation::KingCount {
+                    color,
+                    count: kings,
+                });
             }
+        }
 
-            // Move the rook when a player castles.
-            match (p, r#move) {
-                (Piece::KingWhite, Move { from: 4, to: 2, .. }) => {
-                    self.pieces[0] = None;
-                    self.pieces[3] = Some(Piece::RookWhite);
-                }
-                (Piece::KingWhite, Move { from: 4, to: 6, .. }) => {
-                    self.pieces[7] = None;
-                    self.pieces[5] = Some(Piece::RookWhite);
-                }
-                (
-                    Piece::KingBlack,
-                    Move {
-                        from: 60, to: 62, ..
-                    },
-                ) => {
-                    self.pieces[63] = None;
-                    self.pieces[61] = Some(Piece::RookBlack);
-                }
-                (
-                    Piece::KingBlack,
-                    Move {
-                        from: 60, to: 58, ..
-                    },
-                ) => {
-                    self.pieces[56] = None;
-                    self.pieces[59] = Some(Piece::RookBlack);
-                }
-                _ => {}
+        // Pawns can never stand on the first or last rank, they promote there.
+        for square in (0..8).chain(56..64) {
+            if COLORS
+                .iter()
+                .any(|color| self.at(square) == Some(color.pieces()[0]))
+            {
+                violations.push(Violation::PawnOnBackRank { square });
+            }
+        }
+
+        // The player who just moved may not have left their king in check.
+        if self.is_opponent_in_check() {
+            violations.push(Violation::OpponentInCheck);
+        }
+
+        // Castling rights require the king and the rook to still be on their starting squares.
+        let castling = [
+            (Color::White, true, self.can_white_castle_king_side, 4, 7),
+            (Color::White, false, self.can_white_castle_queen_side, 4, 0),
+            (Color::Black, true, self.can_black_castle_king_side, 60, 63),
+            (
+                Color::Black,
+                false,
+                self.can_black_castle_queen_side,
+                60,
+                56,
+            ),
+        ];
+        for (color, king_side, has_right, king_square, rook_square) in castling {
+            let [_, _, _, rook, _, king] = color.pieces();
+            if has_right
+                && (self.at(king_square) != Some(king) || self.at(rook_square) != Some(rook))
+            {
+                violations.push(Violation::CastlingRights { color, king_side });
+            }
+        }
+
+        // The en pessant square must be right behind a pawn that just moved two squares.
+        // That means the square and the square the pawn came from are empty.
+        if let Some(square) = self.en_pessant_square {
+            let (rank, pawn_square, origin_square) = match self.white_to_move {
+                true => (5, square.wrapping_sub(8), square.wrapping_add(8)),
+                false => (2, square.wrapping_add(8), square.wrapping_sub(8)),
+            };
+            let [pawn, ..] = self.side_to_move().opposite().pieces();
+            let possible = square / 8 == rank
+                && self.at(square).is_none()
+                && self.at(origin_square).is_none()
+                && self.at(pawn_square) == Some(pawn);
+            if !possible {
+                violations.push(Violation::EnPessant { square });
+            }
+        }
+
+        // Each side starts with 16 pieces, 8 of which are pawns.
+        // Pieces beyond the starting set can only come from promoting a pawn.
+        for color in COLORS {
+            let [pawn, knight, bishop, rook, queen, _] = color.pieces();
+            let pawns = count(pawn);
+            let pieces = color.pieces().into_iter().map(count).sum();
+            if pieces > 16 {
+                violations.push(Violation::TooManyPieces {
+                    color,
+                    count: pieces,
+                });
+            }
+            if pawns > 8 {
+                violations.push(Violation::TooManyPawns {
+                    color,
+                    count: pawns,
+                });
             }
+            let promoted = count(knight).saturating_sub(2)
+                + count(bishop).saturating_sub(2)
+                + count(rook).saturating_sub(2)
+                + count(queen).saturating_sub(1);
+            if pawns <= 8 && pawns + promoted > 8 {
+                violations.push(Violation::TooManyPromotions { color });
+            }
+        }
 
-            // Update castling rights
-            self.can_white_castle_queen_side &=
-                !(p == Piece::KingWhite || r#move.from == 0 || r#move.to == 0);
-            self.can_white_castle_king_side &=
-                !(p == Piece::KingWhite || r#move.from == 7 || r#move.to == 7);
-            self.can_black_castle_queen_side &=
-                !(p == Piece::KingBlack || r#move.from == 56 || r#move.to == 56);
-            self.can_black_castle_king_side &=
-                !(p == Piece::KingBlack || r#move.from == 63 || r#move.to == 63);
+        violations
+    }
 
-            // Update whose turn it is, and increment the move counter if needed.
-            self.white_to_move = !self.white_to_move;
-            if self.white_to_move {
-                self.full_move_counter += 1;
+    /// Drop castling rights and the en pessant square if they are impossible in the current position.
+    /// Those are commonly set wrong by hand written FEN strings, while the pieces are fine.
+    /// Returns the violations that were repaired.
+    pub fn repair(&mut self) -> Vec<Violation> {
+        let mut repaired = Vec::new();
+        for violation in self.validate() {
+            match violation {
+                Violation::CastlingRights {
+                    color: Color::White,
+                    king_side: true,
+                } => self.can_white_castle_king_side = false,
+                Violation::CastlingRights {
+                    color: Color::White,
+                    king_side: false,
+                } => self.can_white_castle_queen_side = false,
+                Violation::CastlingRights {
+                    color: Color::Black,
+                    king_side: true,
+                } => self.can_black_castle_king_side = false,
+                Violation::CastlingRights {
+                    color: Color::Black,
+                    king_side: false,
+                } => self.can_black_castle_queen_side = false,
+                Violation::EnPessant { .. } => self.en_pessant_square = None,
+                _ => continue,
             }
+            repaired.push(violation);
         }
+        self.hash = self.compute_hash();
+        repaired
     }
 
     /// Lookup what piece is at a particular square in the board.
     pub fn at(&self, square: Square) -> Option<Piece> {
-        *self.pieces.get(square as usize)?
+        *self.mailbox.get(square as usize)?
+    }
+
+    /// All squares occupied by a particular [Piece].
+    pub fn bitboard(&self, piece: Piece) -> Bitboard {
+        self.pieces[piece as usize]
     }
 
-    fn is_in_check(&self) -> bool {
+    /// All squares occupied by pieces of one [Color].
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        self.occupancy[color as usize]
+    }
+
+    /// All squares occupied by any piece.
+    pub fn occupied(&self) -> Bitboard {
+        self.occupancy[0] | self.occupancy[1]
+    }
+
+    /// Does a side have any pieces besides its king and pawns?
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        let [pawn, .., king] = color.pieces();
+        self.occupied_by(color) & !(self.bitboard(pawn) | self.bitboard(king)) != 0
+    }
+
+    /// The number of moves since the last capture or pawn move.
+    pub fn half_move_clock(&self) -> u32 {
+        self.half_move_clock
+    }
+
+    /// Is there too little material left on the board for either player to checkmate?
+    /// That is the case for king against king with at most a single knight or bishop,
+    /// or when all remaining bishops are on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        use Piece::*;
+        let mating_material = [
+            PawnWhite, PawnBlack, RookWhite, RookBlack, QueenWhite, QueenBlack,
+        ];
+        if mating_material.iter().any(|&p| self.bitboard(p) != 0) {
+            return false;
+        }
+        let knights = self.bitboard(KnightWhite) | self.bitboard(KnightBlack);
+        let bishops = self.bitboard(BishopWhite) | self.bitboard(BishopBlack);
+        let minors = (knights | bishops).count_ones();
+        let same_colored_bishops = bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0;
+        minors <= 1 || (knights == 0 && same_colored_bishops)
+    }
+
+    /// The side whose turn it is.
+    pub fn side_to_move(&self) -> Color {
+        if self.white_to_move {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    /// Is the side to move in check?
+    pub fn is_in_check(&self) -> bool {
         self.is_side_in_check(self.white_to_move)
     }
 
+    /// Has the game ended in this position, and how?
+    /// Checkmate takes precedence over the fifty move rule.
+    /// Repetitions can not be seen from a single position, see [crate::game::Game::outcome] for those.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.generate_moves().is_empty() {
+            if self.is_in_check() {
+                Some(Outcome::Checkmate(self.side_to_move().opposite()))
+            } else {
+                Some(Outcome::Stalemate)
+            }
+        } else if self.is_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else if self.half_move_clock >= 100 {
+            Some(Outcome::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
     fn is_opponent_in_check(&self) -> bool {
         self.is_side_in_check(!self.white_to_move)
     }
 
     /// Does this move leave the current player in check?
-    /// The check is performed by applying the move,
-    /// then checking if the opponent is in check.
+    /// The check is performed without making the move,
+    /// by looking for attackers of the king on the board as it would be after the move.
     fn would_leave_in_check(&self, r#move: Move) -> bool {
-        let mut b = (*self).clone();
-        b.apply(r#move);
-        b.is_opponent_in_check()
+        let Some(piece) = self.at(r#move.from) else {
+            return false;
+        };
+        let us = piece.color();
+        let to = square_bb(r#move.to);
+
+        // The captured piece can't attack anymore, and the moved piece leaves its square.
+        let mut captured = to;
+        let is_pawn = piece == us.pieces()[0];
+        if is_pawn && Some(r#move.to) == self.en_pessant_square {
+            let captured_pawn_square = match us {
+                Color::White => r#move.to - 8,
+                Color::Black => r#move.to + 8,
+            };
+            captured |= square_bb(captured_pawn_square);
+        }
+        let occupied = (self.occupied() & !square_bb(r#move.from) & !captured) | to;
+
+        let king_square = if piece == us.pieces()[5] {
+            Some(r#move.to)
+        } else {
+            squares(self.bitboard(us.pieces()[5])).next()
+        };
+        king_square.is_some_and(|king_square| {
+            self.attackers(king_square, us.opposite(), occupied) & !captured != 0
+        })
     }
 
     /// Is black or white in check?
     fn is_side_in_check(&self, check_white: bool) -> bool {
-        // Get position of players king
-        let king_pos = self.pieces.iter().position(|p| match p {
-            Some(Piece::KingWhite) if check_white => true,
-            Some(Piece::KingBlack) if !check_white => true,
-            _ => false,
-        });
-
-        // Pretend that the players king is any other piece of the same color.
-        // If the piece is able to capture a piece of the same kind,
-        // then the king is in check.
-        // So for example if we pretend the white king is a white knight,
-        // and that knight can capture a black knight,
-        // that means the black knight is checking the white king.
-        if let Some(king_pos) = king_pos {
-            use Piece::*;
-            let pieces = if check_white {
-                [
-                    QueenWhite,
-                    BishopWhite,
-                    RookWhite,
-                    KnightWhite,
-                    PawnWhite,
-                    KingWhite,
-                ]
-            } else {
-                [
-                    QueenBlack,
-                    BishopBlack,
-                    RookBlack,
-                    KnightBlack,
-                    PawnBlack,
-                    KingBlack,
-                ]
-            };
-            for piece in pieces {
-                let moves = generate_piece_moves(self, piece, king_pos as u8, true);
-                let has_capture = moves
-                    .iter()
-                    .find(|m| {
-                        self.at(m.to)
-                            .is_some_and(|other| other.is_same_kind(&piece))
-                    })
-                    .is_some();
-
-                if has_capture {
-                    return true;
-                }
+        let color = if check_white {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let king = self.pieces[color.pieces()[5] as usize];
+        squares(king).any(|square| self.is_attacked(square, color.opposite()))
+    }
+
+    /// Is a square attacked by any piece of the given color?
+    fn is_attacked(&self, square: Square, by: Color) -> bool {
+        self.attackers_to(square, by) != 0
+    }
+
+    /// Find all pieces of the given color that attack a square.
+    pub fn attackers_to(&self, square: Square, color: Color) -> Bitboard {
+        self.attackers(square, color, self.occupied())
+    }
+
+    /// Static exchange evaluation: what a capture wins or loses in centipawns, once every piece
+    /// attacking the square has had the chance to recapture, least valuable first.
+    /// Either side may stop recapturing when continuing would lose more.
+    /// Pieces lined up behind each other join in as the pieces in front of them capture.
+    /// Pins and checks are not considered.
+    pub fn see(&self, r#move: Move) -> i32 {
+        let Some(piece) = self.at(r#move.from) else {
+            return 0;
+        };
+        let to = r#move.to;
+        let mut occupied = self.occupied() & !square_bb(r#move.from);
+
+        // The first capture is made no matter what, and may be en pessant or a promotion.
+        let mut captured = match self.at(to) {
+            Some(victim) => piece_value(victim),
+            None if self.is_capture(r#move) => {
+                let captured_pawn = match piece.color() {
+                    Color::White => to - 8,
+                    Color::Black => to + 8,
+                };
+                occupied &= !square_bb(captured_pawn);
+                piece_value(Piece::PawnWhite)
             }
+            None => 0,
+        };
+        let mut on_square = piece_value(piece);
+        if let Some(promoted) = r#move.promote_to {
+            captured += piece_value(promoted) - piece_value(piece);
+            on_square = piece_value(promoted);
         }
 
-        false
+        // gains[i] is what the side making capture i has won so far, if the other side doesn't recapture.
+        // There can't be more captures than pieces on the board.
+        let mut gains = [0; 32];
+        gains[0] = captured;
+        let mut captures = 1;
+        let mut side = piece.color().opposite();
+        loop {
+            // Sliders behind pieces that already captured are found, as those no longer block them.
+            let attackers = self.attackers(to, side, occupied) & occupied;
+            let Some((attacker, from)) = side.pieces().into_iter().find_map(|p| {
+                squares(self.bitboard(p) & attackers)
+                    .next()
+                    .map(|square| (p, square))
+            }) else {
+                break;
+            };
+            gains[captures] = on_square - gains[captures - 1];
+            captures += 1;
+            on_square = piece_value(attacker);
+            occupied &= !square_bb(from);
+            side = side.opposite();
+        }
+
+        // Going backwards, each side picks the better of recapturing or stopping.
+        for i in (1..captures).rev() {
+            gains[i - 1] = -(-gains[i - 1]).max(gains[i]);
+        }
+        gains[0]
+    }
+
+    /// Find all pieces of the given color that attack a square, when the board has the given occupied squares.
+    fn attackers(&self, square: Square, by: Color, occupied: Bitboard) -> Bitboard {
+        // Pretend that there is a piece of every kind on the square.
+        // If that piece is able to capture an enemy piece of the same kind,
+        // then the enemy piece attacks the square as well.
+        // So for example if we pretend there is a knight on the square,
+        // and that knight can capture a knight of the attacking color,
+        // that means the attacking knight can reach the square.
+        let [pawn, knight, bishop, rook, queen, king] = by.pieces().map(|p| self.bitboard(p));
+        let at = square as usize;
+        (PAWN_ATTACKS[by.opposite() as usize][at] & pawn)
+            | (KNIGHT_ATTACKS[at] & knight)
+            | (KING_ATTACKS[at] & king)
+            | (bishop_attacks(square, occupied) & (bishop | queen))
+            | (rook_attacks(square, occupied) & (rook | queen))
+    }
+
+    /// Is a move legal in this position?
+    /// Cheaper than generating every move, so moves from elsewhere, like the transposition table, can be checked.
+    pub fn is_legal(&self, r#move: Move) -> bool {
+        let Some(piece) = self.at(r#move.from) else {
+            return false;
+        };
+        if piece.color() != self.side_to_move() {
+            return false;
+        }
+        let mut moves = MoveList::new();
+        let targets = !self.occupied_by(piece.color());
+        self.generate_piece_moves(piece, r#move.from, GenType::All, targets, &mut moves);
+        moves.contains(&r#move) && !self.would_leave_in_check(r#move)
+    }
+
+    /// Does a move capture a piece, including en pessant?
+    pub fn is_capture(&self, r#move: Move) -> bool {
+        let is_pawn = matches!(
+            self.at(r#move.from),
+            Some(Piece::PawnWhite | Piece::PawnBlack)
+        );
+        self.at(r#move.to).is_some() || (is_pawn && r#move.from % 8 != r#move.to % 8)
+    }
+
+    /// The piece a move captures, if any.
+    pub fn captured_piece(&self, r#move: Move) -> Option<Piece> {
+        if !self.is_capture(r#move) {
+            return None;
+        }
+        // Only en pessant captures land on an empty square.
+        let [pawn, ..] = self.side_to_move().opposite().pieces();
+        Some(self.at(r#move.to).unwrap_or(pawn))
     }
 
     /// Generate all legal [Move]s possible within the current [Board].
     pub fn generate_moves(&self) -> Vec<Move> {
-        self.generate_pseudo_moves()
-            .into_iter()
-            .filter(|&m| !self.would_leave_in_check(m))
-            .collect()
+        let mut moves = MoveList::new();
+        self.generate(GenType::All, &mut moves);
+        moves.to_vec()
     }
 
     /// Generate all pseudo-legal [Move]s possible within the current [Board].
     /// A pseudo legal move may leave the player in check.
     pub fn generate_pseudo_moves(&self) -> Vec<Move> {
-        let mut moves = Vec::new();
+        let mut moves = MoveList::new();
+        self.generate_pseudo(GenType::All, &mut moves);
+        moves.to_vec()
+    }
 
-        for rank in (0..8).rev() {
-            for file in 0..8 {
-                let square = rank * 8 + file;
-                match self.at(square) {
-                    // Square had a piece with the color whose turn it is
-                    Some(piece) if self.white_to_move == piece.is_white() => {
-                        let valid_target_squares = generate_piece_moves(self, piece, square, false);
-                        let mut piece_moves: Vec<Move> = valid_target_squares;
-                        moves.append(&mut piece_moves);
-                    }
-                    // Square was empty, or had the wrong color piece.
-                    _ => continue,
-                }
+    /// Add the legal moves of a kind to a list.
+    pub fn generate(&self, gen: GenType, moves: &mut MoveList) {
+        let start = moves.len();
+        self.generate_pseudo(gen, moves);
+        let mut legal = start;
+        for i in start..moves.len() {
+            let m = moves[i];
+            if !self.would_leave_in_check(m) {
+                moves[legal] = m;
+                legal += 1;
             }
         }
-
-        moves
+        moves.truncate(legal);
     }
-}
 
-/// Generate the valid moves for a particular piece on a certain square within a board.
-fn generate_piece_moves(board: &Board, piece: Piece, at: Square, skip_castling: bool) -> Vec<Move> {
-    match piece {
-        Piece::PawnWhite | Piece::PawnBlack => {
-            let rank = at / 8;
-            let file = at % 8;
+    /// Add the pseudo-legal moves of a kind to a list. A pseudo legal move may leave the player in check.
+    pub fn generate_pseudo(&self, gen: GenType, moves: &mut MoveList) {
+        let us = self.side_to_move();
+        let enemies = self.occupied_by(us.opposite());
+        let empty = !self.occupied();
 
-            let mut moves: Vec<Move> = vec![];
-
-            // A pawn may move one square towards the opposing player.
-            let advance_square = match piece.is_white() {
-                true if rank < 8 => Some((rank + 1) * 8 + file),
-                false if rank > 0 => Some((rank - 1) * 8 + file),
-                _ => None,
-            };
+        // Squares that pieces other than the king may move to.
+        let targets = match gen {
+            GenType::All => enemies | empty,
+            GenType::Captures => enemies,
+            GenType::Quiets => empty,
+            GenType::Evasions => {
+                let king = squares(self.bitboard(us.pieces()[5])).next();
+                let checkers =
+                    king.map_or(0, |k| self.attackers(k, us.opposite(), self.occupied()));
+                let mut checkers_iter = squares(checkers);
+                match (checkers_iter.next(), checkers_iter.next(), king) {
+                    // Not in check, so every move is an evasion.
+                    (None, _, _) => enemies | empty,
+                    // Capture the checker, or step in between it and the king.
+                    (Some(checker), None, Some(king)) => {
+                        square_bb(checker) | between(king, checker)
+                    }
+                    // In double check only the king can move.
+                    _ => 0,
+                }
+            }
+        };
 
-            // If it is in it's starting rank, it may leap two squares.
-            let leap_square = match piece.is_white() {
-                true if rank == 1 => Some((rank + 2) * 8 + file),
-                false if rank == 6 => Some((rank - 2) * 8 + file),
-                _ => None,
-            };
+        for piece in us.pieces() {
+            for square in squares(self.bitboard(piece)) {
+                self.generate_piece_moves(piece, square, gen, targets, moves);
+            }
+        }
+    }
 
-            if advance_square.is_some_and(|s| board.at(s).is_none()) {
-                moves.push(Move::new(at, advance_square.unwrap()));
-                if leap_square.is_some_and(|s| board.at(s).is_none()) {
-                    moves.push(Move::new(at, leap_square.unwrap()));
+    /// Generate the valid moves for a particular piece on a certain square, landing on one of the target squares.
+    fn generate_piece_moves(
+        &self,
+        piece: Piece,
+        at: Square,
+        gen: GenType,
+        targets: Bitboard,
+        moves: &mut MoveList,
+    ) {
+        let occupied = self.occupied();
+        let attacks = match piece {
+            Piece::PawnWhite | Piece::PawnBlack => {
+                return self.generate_pawn_moves(piece, at, gen, targets, moves)
+            }
+            Piece::KnightWhite | Piece::KnightBlack => KNIGHT_ATTACKS[at as usize] & targets,
+            Piece::BishopWhite | Piece::BishopBlack => bishop_attacks(at, occupied) & targets,
+            Piece::RookWhite | Piece::RookBlack => rook_attacks(at, occupied) & targets,
+            Piece::QueenWhite | Piece::QueenBlack => {
+                (bishop_attacks(at, occupied) | rook_attacks(at, occupied)) & targets
+            }
+            Piece::KingWhite | Piece::KingBlack => {
+                if gen != GenType::Captures {
+                    self.generate_castling_moves(at, moves);
                 }
+                // The king escapes check by moving away, not by blocking.
+                let own = self.occupied_by(piece.color());
+                let king_targets = match gen {
+                    GenType::Evasions => !own,
+                    _ => targets,
+                };
+                KING_ATTACKS[at as usize] & king_targets
             }
+        };
+        moves.extend(squares(attacks).map(|to| Move::new(at, to)));
+    }
 
-            // A pawn may capture diagonally.
-            let capture_l = match piece.is_white() {
-                true if rank < 8 && file > 0 => Some((rank + 1) * 8 + file - 1),
-                false if rank > 0 && file > 0 => Some((rank - 1) * 8 + file - 1),
-                _ => None,
-            };
+    /// Generate the valid moves for a pawn on a certain square, landing on one of the target squares.
+    /// Promotions to a queen count as captures, while other promotions without capturing count as quiet moves.
+    fn generate_pawn_moves(
+        &self,
+        piece: Piece,
+        at: Square,
+        gen: GenType,
+        targets: Bitboard,
+        moves: &mut MoveList,
+    ) {
+        let color = piece.color();
+        let rank = at / 8;
+        let (start_rank, promotion_rank) = match color {
+            Color::White => (1, 7),
+            Color::Black => (6, 0),
+        };
+        let forward = |square: Square| match color {
+            Color::White => square.checked_add(8).filter(|&s| s < 64),
+            Color::Black => square.checked_sub(8),
+        };
 
-            let can_capture_l = capture_l.map(|s| {
-                let sees_enemy_piece = matches!(
-                    board.at(s),
-                    Some(other) if other.is_white() != piece.is_white()
-                );
-                let en_pessant = board.en_pessant_square.is_some_and(|es| es == s);
-                sees_enemy_piece || en_pessant
-            }) == Some(true);
-            if can_capture_l {
-                moves.push(Move::new(at, capture_l.unwrap()));
-            }
-            let capture_r = match piece.is_white() {
-                true if rank < 8 && file < 7 => Some((rank + 1) * 8 + file + 1),
-                false if rank > 0 && file < 7 => Some((rank - 1) * 8 + file + 1),
-                _ => None,
-            };
-            let can_capture_r = capture_r.map(|s| {
-                let sees_enemy_piece = matches!(
-                    board.at(s),
-                    Some(other) if other.is_white() != piece.is_white()
-                );
-                let en_pessant = board.en_pessant_square.is_some_and(|es| es == s);
-                sees_enemy_piece || en_pessant
-            }) == Some(true);
+        // A pawn may move one square towards the opposing player.
+        // If it is in it's starting rank, it may leap two squares.
+        let mut advances = 0;
+        if let Some(advance) = forward(at).filter(|&s| self.at(s).is_none()) {
+            advances |= square_bb(advance);
+            if let Some(leap) = forward(advance).filter(|&s| self.at(s).is_none()) {
+                if rank == start_rank {
+                    advances |= square_bb(leap);
+                }
+            }
+        }
 
-            if can_capture_r {
-                moves.push(Move::new(at, capture_r.unwrap()));
+        // A pawn may capture diagonally, including capturing en pessant.
+        let pawn_attacks = PAWN_ATTACKS[color as usize][at as usize];
+        let mut captures = pawn_attacks & self.occupied_by(color.opposite()) & targets;
+        if let Some(es) = self.en_pessant_square {
+            // The pawn that just leaped may be giving check, and capturing it removes the check.
+            let leaped = match color {
+                Color::White => es - 8,
+                Color::Black => es + 8,
+            };
+            if gen != GenType::Quiets && targets & (square_bb(es) | square_bb(leaped)) != 0 {
+                captures |= pawn_attacks & square_bb(es);
             }
+        }
 
-            moves
-                .iter()
-                // Handle promotions.
-                .flat_map(|&Move { from, to, .. }| {
-                    use Piece::*;
-                    match (piece.is_white(), to / 8) {
-                        // White pawn made it to rank 8. Expand the move to all possible promotions.
-                        (true, 7) => [KnightWhite, BishopWhite, RookWhite, QueenWhite]
-                            .iter()
-                            .map(|&p| Move {
-                                from,
-                                to,
-                                promote_to: Some(p),
-                            })
-                            .collect::<Vec<Move>>(),
-                        // Black pawn made it to rank 1. Expand the move to all possible promotions.
-                        (false, 0) => [KnightBlack, BishopBlack, RookBlack, QueenBlack]
-                            .iter()
-                            .map(|&p| Move {
-                                from,
-                                to,
-                                promote_to: Some(p),
-                            })
-                            .collect::<Vec<Move>>(),
-
-                        _ => vec![Move::new(from, to)],
-                    }
-                })
-                .collect()
-        }
-        Piece::KnightWhite | Piece::KnightBlack => {
-            // Knights may move two squares orthogonally and then one square along the other orthogonal axis.
-            // That comes out to 8 unique squares to land on.
-            // . x . x .
-            // x . . . x
-            // . . n . .
-            // x . . . x
-            // . x . x .
-            let rank = at as i8 / 8;
-            let file = at as i8 % 8;
-            [
-                (rank + 2, file + 1),
-                (rank + 2, file - 1),
-                (rank - 2, file + 1),
-                (rank - 2, file - 1),
-                (rank + 1, file + 2),
-                (rank + 1, file - 2),
-                (rank - 1, file + 2),
-                (rank - 1, file - 2),
-            ]
-            .into_iter()
-            .filter(|(r, f)| !(*r > 7 || *f > 7 || *r < 0 || *f < 0))
-            .map(|(r, f)| to_board_square(r * 8 + f).unwrap())
-            // A knight may land on a square with a opposite colored piece or no piece.
-            .filter(|&square| match board.at(square) {
-                None => true,
-                Some(other) if other.is_white() != piece.is_white() => true,
-                _ => false,
-            })
-            .map(|square| Move::new(at, square))
-            .collect()
-        }
-        Piece::KingWhite | Piece::KingBlack => {
-            // The king may move to any surrounding square.
-            let rank = at as i8 / 8;
-            let file = at as i8 % 8;
-            let mut moves: Vec<Move> = [
-                (rank + 1, file + 1),
-                (rank + 1, file),
-                (rank + 1, file - 1),
-                (rank - 1, file + 1),
-                (rank - 1, file),
-                (rank - 1, file - 1),
-                (rank, file + 1),
-                (rank, file - 1),
-            ]
-            .into_iter()
-            .filter(|(r, f)| !(*r > 7 || *f > 7 || *r < 0 || *f < 0))
-            .map(|(r, f)| to_board_square(r * 8 + f).unwrap())
-            // The king may land on a square with a opposite colored piece or no piece.
-            .filter(|&square| match board.at(square) {
-                Some(other) if other.is_white() != piece.is_white() => true,
-                None => true,
-                _ => false,
-            })
-            .map(|square| Move::new(at, square))
-            .collect();
-
-            if !skip_castling {
-                // Castling queen side for white.
-                if board.white_to_move
-                // Still has castling rights
-                && board.can_white_castle_queen_side
-                // No piece is obstructing the castling
-                && board.pieces[3].is_none() && board.pieces[2].is_none() && board.pieces[1].is_none()
-                // Player is not in check
-                && !board.is_in_check()
-                // Check that d1 is not attacked, since the king can't castle through check.
-                && !board.would_leave_in_check(Move::new(4, 3))
-                {
-                    moves.push(Move::new(at, 2));
-                }
-                // Castling king side for white.
-                if board.white_to_move
-                // Still has castling rights
-                && board.can_white_castle_king_side
-                // No piece is obstructing the castling
-                && board.pieces[5].is_none() && board.pieces[6].is_none()
-                // Player is not in check
-                && !board.is_in_check()
-                // Check that e1 is not attacked, since the king can't castle through check.
-                && !board.would_leave_in_check(Move::new(4, 5))
-                {
-                    moves.push(Move::new(at, 6));
-                }
-                // Castling queen side for black.
-                if !board.white_to_move
-                // Still has castling rights
-                && board.can_black_castle_queen_side
-                // No piece is obstructing the castling
-                && board.pieces[59].is_none() && board.pieces[58].is_none() && board.pieces[57].is_none()
-                // Player is not in check
-                && !board.is_in_check()
-                // Check that d8 is not attacked, since the king can't castle through check.
-                && !board.would_leave_in_check(Move::new(60, 59))
-                {
-                    moves.push(Move::new(at, 58));
-                }
-                // Castling king side for black.
-                if !board.white_to_move
-                // Still has castling rights
-                && board.can_black_castle_king_side
-                // No piece is obstructing the castling
-                && board.pieces[61].is_none() && board.pieces[62].is_none()
-                // Player is not in check
-                && !board.is_in_check()
-                // Check that e8 is not attacked, since the king can't castle through check.
-                && !board.would_leave_in_check(Move::new(60, 61))
-                {
-                    moves.push(Move::new(at, 62));
+        let promotion_rank_bb = 0xFF << (promotion_rank * 8);
+        let advances = match gen {
+            // Advancing is only a capture when promoting to a queen.
+            GenType::Captures => advances & promotion_rank_bb,
+            _ => advances & targets,
+        };
+
+        let [_, knight, bishop, rook, queen, _] = color.pieces();
+        let all = [knight, bishop, rook, queen];
+        for to in squares(advances | captures) {
+            if to / 8 == promotion_rank {
+                // Pawn made it to the last rank. Expand the move to all possible promotions.
+                let is_capture = captures & square_bb(to) != 0;
+                let promotions = match (gen, is_capture) {
+                    (GenType::Captures, false) => &all[3..],
+                    (GenType::Quiets, _) => &all[..3],
+                    _ => &all[..],
+                };
+                for &p in promotions {
+                    moves.push(Move {
+                        from: at,
+                        to,
+                        promote_to: Some(p),
+                    });
                 }
+            } else {
+                moves.push(Move::new(at, to));
             }
-
-            moves
-        }
-        Piece::RookWhite | Piece::RookBlack => {
-            let directions = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-            sliding_moves(&directions, board, piece, at)
-        }
-        Piece::BishopWhite | Piece::BishopBlack => {
-            let directions = [(1, 1), (-1, -1), (-1, 1), (1, -1)];
-            sliding_moves(&directions, board, piece, at)
-        }
-        Piece::QueenWhite | Piece::QueenBlack => {
-            let directions = [
-                (1, 0),
-                (-1, 0),
-                (0, 1),
-                (0, -1),
-                (1, 1),
-                (-1, -1),
-                (-1, 1),
-                (1, -1),
-            ];
-            sliding_moves(&directions, board, piece, at)
         }
     }
-}
 
-/// Helper function for computing sliding moves in both orthogonal and diagonal directions.
-fn sliding_moves(directions: &[(i8, i8)], board: &Board, piece: Piece, at: Square) -> Vec<Move> {
-    let mut moves: Vec<Move> = Vec::new();
-    let rank = at as i8 / 8;
-    let file = at as i8 % 8;
-    for (ro, fo) in directions {
-        let mut r = rank;
-        let mut f = file;
-        loop {
-            r += ro;
-            f += fo;
-            if r > 7 || f > 7 || r < 0 || f < 0 {
-                break;
-            }
-            let square = to_board_square(r * 8 + f).unwrap();
-            match board.at(square) {
-                None => moves.push(Move::new(at, square)),
-                Some(other) => {
-                    if other.is_white() != piece.is_white() {
-                        moves.push(Move::new(at, square))
-                    }
-                    break;
-                }
+    /// Generate the castling moves available to a king on a certain square.
+    fn generate_castling_moves(&self, at: Square, moves: &mut MoveList) {
+        let occupied = self.occupied();
+        let is_empty = |squares: &[Square]| squares.iter().all(|&s| occupied & square_bb(s) == 0);
+        // For each way to castle: is it the players turn, do they still have castling rights,
+        // is the king on its starting square, which squares must be empty,
+        // which square does the king pass through and where does it land.
+        let castlings = [
+            // Castling queen side for white.
+            (
+                self.white_to_move && self.can_white_castle_queen_side,
+                4,
+                [1, 2, 3].as_slice(),
+                3,
+                2,
+            ),
+            // Castling king side for white.
+            (
+                self.white_to_move && self.can_white_castle_king_side,
+                4,
+                [5, 6].as_slice(),
+                5,
+                6,
+            ),
+            // Castling queen side for black.
+            (
+                !self.white_to_move && self.can_black_castle_queen_side,
+                60,
+                [57, 58, 59].as_slice(),
+                59,
+                58,
+            ),
+            // Castling king side for black.
+            (
+                !self.white_to_move && self.can_black_castle_king_side,
+                60,
+                [61, 62].as_slice(),
+                61,
+                62,
+            ),
+        ];
+        let them = self.side_to_move().opposite();
+        for (can_castle, from, must_be_empty, passes, to) in castlings {
+            if can_castle
+            && at == from
+            // No piece is obstructing the castling
+            && is_empty(must_be_empty)
+            // Player is not in check
+            && !self.is_in_check()
+            // The king can't castle through check.
+            // Landing in check is ruled out like for any other king move.
+            && !self.is_attacked(passes, them)
+            {
+                moves.push(Move::new(at, to));
             }
         }
     }
+}
 
-    moves
+/// If a move by a piece is castling, get the rook that moves along with the square it moves from and to.
+fn castling_rook_move(piece: Piece, r#move: Move) -> Option<(Piece, Square, Square)> {
+    match (piece, r#move.from, r#move.to) {
+        (Piece::KingWhite, 4, 2) => Some((Piece::RookWhite, 0, 3)),
+        (Piece::KingWhite, 4, 6) => Some((Piece::RookWhite, 7, 5)),
+        (Piece::KingBlack, 60, 62) => Some((Piece::RookBlack, 63, 61)),
+        (Piece::KingBlack, 60, 58) => Some((Piece::RookBlack, 56, 59)),
+        _ => None,
+    }
 }
 
 impl Display for Board {
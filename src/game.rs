@@ -0,0 +1,81 @@
+use crate::{
+    board::{Board, Outcome},
+    Move,
+};
+
+/// A game of chess: the current [Board] along with the moves that led to it.
+/// Keeps the hashes of earlier positions, so repeated positions can be detected.
+#[derive(Debug, Clone)]
+pub struct Game {
+    board: Board,
+    moves: Vec<Move>,
+    /// The hash of the position before each move in `moves`.
+    history: Vec<u64>,
+}
+
+impl Game {
+    /// Start a game from a position.
+    pub fn new(board: Board) -> Self {
+        Self {
+            board,
+            moves: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// The current position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The moves applied since the starting position.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// The hashes of the positions before each applied move, oldest first.
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
+
+    /// Applies a move to the current position. The move is assummed to be legal.
+    pub fn apply(&mut self, r#move: Move) {
+        self.history.push(self.board.hash());
+        self.moves.push(r#move);
+        self.board.apply(r#move);
+    }
+
+    /// Has the current position occurred at least twice before?
+    /// Only positions since the last capture or pawn move are considered,
+    /// since those can never be repeated.
+    pub fn is_threefold_repetition(&self) -> bool {
+        let hash = self.board.hash();
+        let repetitions = self
+            .history
+            .iter()
+            .rev()
+            .take(self.board.half_move_clock() as usize)
+            .filter(|&&h| h == hash)
+            .count();
+        repetitions >= 2
+    }
+
+    /// Have 50 moves by each player passed without a capture or a pawn move?
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.board.half_move_clock() >= 100
+    }
+
+    /// Is there too little material left on the board for either player to checkmate?
+    pub fn is_insufficient_material(&self) -> bool {
+        self.board.is_insufficient_material()
+    }
+
+    /// Has the game ended, and how?
+    /// Same as [Board::outcome], but also detects threefold repetition.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.board.outcome().or_else(|| {
+            self.is_threefold_repetition()
+                .then_some(Outcome::ThreefoldRepetition)
+        })
+    }
+}
@@ -1,6 +1,66 @@
 use std::fmt::Display;
 
+pub mod bitboard;
 pub mod board;
+pub mod eval;
+pub mod game;
+mod magic;
+pub mod movepick;
+pub mod perft;
+pub mod search;
+pub mod time;
+pub mod tt;
+pub mod uci;
+mod zobrist;
+
+/// The two sides of a chess game.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Color {
+    White = 0,
+    Black = 1,
+}
+
+impl Color {
+    /// The other side.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+
+    /// Every piece of this color, ordered pawn, knight, bishop, rook, queen, king.
+    pub fn pieces(&self) -> [Piece; 6] {
+        use Piece::*;
+        match self {
+            Self::White => [
+                PawnWhite,
+                KnightWhite,
+                BishopWhite,
+                RookWhite,
+                QueenWhite,
+                KingWhite,
+            ],
+            Self::Black => [
+                PawnBlack,
+                KnightBlack,
+                BishopBlack,
+                RookBlack,
+                QueenBlack,
+                KingBlack,
+            ],
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::White => write!(f, "white"),
+            Self::Black => write!(f, "black"),
+        }
+    }
+}
 
 /// Every type of piece in chess.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -66,25 +126,34 @@ impl Piece {
         *self as u8 != 0 && *self as u8 >> 3 < 1
     }
 
+    /// The side this piece belongs to.
+    pub fn color(&self) -> Color {
+        if self.is_white() {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
     /// Check if another piece is of the same kind.
     /// For instance, are both rooks?
     pub fn is_same_kind(&self, other: &Piece) -> bool {
         use Piece::*;
-        match (self, other) {
-            (PawnBlack, PawnWhite) => true,
-            (PawnWhite, PawnBlack) => true,
-            (KnightBlack, KnightWhite) => true,
-            (KnightWhite, KnightBlack) => true,
-            (BishopBlack, BishopWhite) => true,
-            (BishopWhite, BishopBlack) => true,
-            (RookBlack, RookWhite) => true,
-            (RookWhite, RookBlack) => true,
-            (QueenBlack, QueenWhite) => true,
-            (QueenWhite, QueenBlack) => true,
-            (KingBlack, KingWhite) => true,
-            (KingWhite, KingBlack) => true,
-            _ => false,
-        }
+        matches!(
+            (self, other),
+            (PawnBlack, PawnWhite)
+                | (PawnWhite, PawnBlack)
+                | (KnightBlack, KnightWhite)
+                | (KnightWhite, KnightBlack)
+                | (BishopBlack, BishopWhite)
+                | (BishopWhite, BishopBlack)
+                | (RookBlack, RookWhite)
+                | (RookWhite, RookBlack)
+                | (QueenBlack, QueenWhite)
+                | (QueenWhite, QueenBlack)
+                | (KingBlack, KingWhite)
+                | (KingWhite, KingBlack)
+        )
     }
 }
 
@@ -122,7 +191,8 @@ pub type Square = u8;
 /// Converts a i8 that may point to a square in a board to a valid Square if possible.
 /// Hint: If you have an iterator over maybe_squares, use filter_map(to_board_square) on it.
 pub fn to_board_square(maybe_square: i8) -> Option<Square> {
-    (maybe_square >= 0 && maybe_square < 64)
+    (0..64)
+        .contains(&maybe_square)
         .then(|| u8::try_from(maybe_square).ok())
         .flatten()
 }
@@ -133,7 +203,16 @@ pub fn square_to_algebraic(square: Square) -> String {
     format!("{file}{rank}").to_owned()
 }
 
+/// Read a square from algebraic notation, like "e4".
+/// Will panic if the square is not on the board.
 pub fn square_from_algebraic(long_algebraic: &str) -> Square {
+    try_square_from_algebraic(long_algebraic)
+        .unwrap_or_else(|| panic!("Invalid square {}", long_algebraic))
+}
+
+/// Read a square from algebraic notation, like "e4".
+/// Returns None if the string is not exactly a file followed by a rank.
+pub fn try_square_from_algebraic(long_algebraic: &str) -> Option<Square> {
     let mut chars = long_algebraic.chars();
     let file = match chars.next() {
         Some('a') => 0,
@@ -144,10 +223,16 @@ pub fn square_from_algebraic(long_algebraic: &str) -> Square {
         Some('f') => 5,
         Some('g') => 6,
         Some('h') => 7,
-        _ => panic!("Invalid square {}", long_algebraic),
+        _ => return None,
     };
-    let rank = chars.next().and_then(|c| c.to_digit(10)).unwrap() - 1;
-    u8::try_from(rank * 8 + file).unwrap()
+    let rank = match chars.next().and_then(|c| c.to_digit(10)) {
+        Some(r) if (1..=8).contains(&r) => r - 1,
+        _ => return None,
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+    u8::try_from(rank * 8 + file).ok()
 }
 
 /// A move between two squares.
@@ -169,14 +254,14 @@ impl Move {
 
     /// Read a move from long algebraic notation.
     /// Will panic if the move is not valid (has rank or file outside normal chess board).
-    /// TODO: Read promotions
-    /// TODO: is_white parameter
-    pub fn from_str(is_white: bool, long_algebraic: &str) -> Move {
+    /// The color of a promoted piece follows from the rank the pawn lands on.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(long_algebraic: &str) -> Move {
         assert!(long_algebraic.len() >= 4);
         let from = square_from_algebraic(&long_algebraic[0..2]);
         let to = square_from_algebraic(&long_algebraic[2..4]);
         let promote_to = long_algebraic.chars().nth(4).and_then(|c| {
-            let cm = if is_white {
+            let cm = if to / 8 == 7 {
                 c.to_ascii_uppercase()
             } else {
                 c.to_ascii_lowercase()
@@ -207,3 +292,95 @@ impl Display for Move {
         )
     }
 }
+
+/// A list of moves with room for the moves of any chess position, kept on the stack.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MoveList::CAPACITY],
+    len: usize,
+}
+
+impl MoveList {
+    /// More than the most moves possible in a legal chess position, which is 218.
+    pub const CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            moves: [Move::new(0, 0); Self::CAPACITY],
+            len: 0,
+        }
+    }
+
+    /// Add a move to the end of the list.
+    /// Will panic if the list is full.
+    pub fn push(&mut self, m: Move) {
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+
+    /// Remove every move.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Keep only the first `len` moves.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Keep only the moves matching a predicate, keeping their order.
+    pub fn retain(&mut self, mut keep: impl FnMut(Move) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let m = self.moves[i];
+            if keep(m) {
+                self.moves[kept] = m;
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::ops::Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl std::ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<Move> for MoveList {
+    fn extend<T: IntoIterator<Item = Move>>(&mut self, moves: T) {
+        for m in moves {
+            self.push(m);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
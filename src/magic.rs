@@ -0,0 +1,156 @@
+use std::sync::LazyLock;
+
+use crate::{
+    bitboard::{sliding_attacks, square_bb, Bitboard, BISHOP_DIRECTIONS, ROOK_DIRECTIONS},
+    Square,
+};
+
+/// Lookup tables for rook and bishop attacks using magic bitboards.
+///
+/// The squares that can block a slider on a square are its "relevant" squares.
+/// For every subset of the relevant squares that is occupied we precompute the attacks.
+/// A magic number is a multiplier that maps each subset to a unique index in the table,
+/// or to an index shared with subsets that have the exact same attacks.
+/// Magics are searched for by trial and error the first time a lookup is made.
+struct Magics {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    /// Attacks for every square and relevant occupancy of both rooks and bishops.
+    attacks: Vec<Bitboard>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    /// The relevant squares that may block the slider.
+    mask: Bitboard,
+    magic: u64,
+    /// 64 minus the number of relevant squares, so the index has just enough bits.
+    shift: u32,
+    /// Where the attacks of this square start in [Magics::attacks].
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+static MAGICS: LazyLock<Magics> = LazyLock::new(Magics::generate);
+
+/// Squares attacked by a rook on a square given the occupied squares of the board.
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let magics = &*MAGICS;
+    magics.attacks[magics.rook[square as usize].index(occupied)]
+}
+
+/// Squares attacked by a bishop on a square given the occupied squares of the board.
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let magics = &*MAGICS;
+    magics.attacks[magics.bishop[square as usize].index(occupied)]
+}
+
+impl Magics {
+    fn generate() -> Self {
+        let mut attacks = Vec::new();
+        let mut rook = [Magic::default(); 64];
+        let mut bishop = [Magic::default(); 64];
+        for square in 0..64 {
+            rook[square as usize] = find_magic(square, &ROOK_DIRECTIONS, &mut attacks);
+            bishop[square as usize] = find_magic(square, &BISHOP_DIRECTIONS, &mut attacks);
+        }
+        Self {
+            rook,
+            bishop,
+            attacks,
+        }
+    }
+}
+
+/// The squares that may block a slider on a square.
+/// The last square of each ray is left out, since it is attacked whether it is occupied or not.
+fn relevant_mask(square: Square, directions: &[(i8, i8)]) -> Bitboard {
+    let mut mask = 0;
+    let rank = square as i8 / 8;
+    let file = square as i8 % 8;
+    for (ro, fo) in directions {
+        let mut r = rank + ro;
+        let mut f = file + fo;
+        while (0..8).contains(&(r + ro)) && (0..8).contains(&(f + fo)) {
+            mask |= square_bb((r * 8 + f) as Square);
+            r += ro;
+            f += fo;
+        }
+    }
+    mask
+}
+
+/// Find a magic for a slider on a square and append its attack table to `attacks`.
+fn find_magic(square: Square, directions: &[(i8, i8)], attacks: &mut Vec<Bitboard>) -> Magic {
+    let mask = relevant_mask(square, directions);
+    let bits = mask.count_ones();
+    let size = 1 << bits;
+
+    // Enumerate every subset of the mask using the "Carry-Rippler" trick.
+    let mut occupancies = Vec::with_capacity(size);
+    let mut subset: Bitboard = 0;
+    loop {
+        occupancies.push((subset, sliding_attacks(square, subset, directions)));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    // Seeds per rank that are known to find magics quickly, borrowed from Stockfish.
+    let seeds = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+    let mut rng = XorShift(seeds[square as usize / 8]);
+
+    let mut table = vec![0; size];
+    // The attempt that last wrote each table entry, so the table needn't be cleared between attempts.
+    let mut written = vec![0; size];
+    let mut attempt = 0;
+    loop {
+        // Magics with few bits set tend to work better.
+        let magic = rng.next_u64() & rng.next_u64() & rng.next_u64();
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+        let candidate = Magic {
+            mask,
+            magic,
+            shift: 64 - bits,
+            offset: 0,
+        };
+        attempt += 1;
+        let works = occupancies.iter().all(|&(occupied, attack)| {
+            let index = candidate.index(occupied);
+            if written[index] == attempt && table[index] != attack {
+                return false;
+            }
+            written[index] = attempt;
+            table[index] = attack;
+            true
+        });
+        if works {
+            let offset = attacks.len();
+            attacks.extend_from_slice(&table);
+            return Magic {
+                offset,
+                ..candidate
+            };
+        }
+    }
+}
+
+/// Small xorshift* pseudo random number generator.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
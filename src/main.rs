@@ -1,73 +1,271 @@
-use std::io;
+use std::{
+    io,
+    sync::{atomic::Ordering, Arc},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use justok::{board::Board, Move};
+use justok::{
+    board::Board,
+    game::Game,
+    perft,
+    search::{self, Limits, SearchOptions, SearchResult, Signals},
+    tt::TranspositionTable,
+    uci::{self, GoParams, Options, OPTIONS},
+};
+
+/// How long to search when the 'go' command can't be read, rather than searching without end.
+const FALLBACK_MOVETIME: Duration = Duration::from_millis(1000);
+
+/// A search running on a worker thread, so the main thread can keep reading commands.
+struct SearchThread {
+    signals: Arc<Signals>,
+    handle: JoinHandle<()>,
+}
+
+impl SearchThread {
+    /// Start searching. The worker sends 'bestmove' itself when done.
+    /// In infinite mode and while pondering it may not do so before being told to stop,
+    /// or before a 'ponderhit', even if the search finished on its own.
+    fn start(
+        game: Game,
+        limits: Limits,
+        infinite: bool,
+        ponder: bool,
+        options: SearchOptions,
+        tt: Arc<TranspositionTable>,
+    ) -> Self {
+        let signals = Arc::new(Signals::default());
+        signals.ponder.store(ponder, Ordering::Relaxed);
+        let handle = thread::spawn({
+            let signals = Arc::clone(&signals);
+            move || {
+                let result = search::search_until(&game, &limits, &options, &tt, &signals);
+                while !signals.stop.load(Ordering::Relaxed)
+                    && (infinite || signals.ponder.load(Ordering::Relaxed))
+                {
+                    thread::park();
+                }
+                print_best_move(&result);
+            }
+        });
+        Self { signals, handle }
+    }
+
+    /// The opponent played the move that was pondered on, so keep searching with the clock running.
+    fn ponderhit(&self) {
+        self.signals.ponder.store(false, Ordering::Relaxed);
+        self.handle.thread().unpark();
+    }
+
+    /// Stop the search and wait for it to finish.
+    /// 'bestmove' has been sent exactly once when this returns.
+    fn stop(self) {
+        self.signals.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        self.handle.join().expect("search thread panicked");
+    }
+}
+
+/// Send the best move, and the reply expected from the opponent to ponder on if there is one.
+fn print_best_move(result: &SearchResult) {
+    match result.pv.as_slice() {
+        [] => println!("bestmove 0000"),
+        [best] => println!("bestmove {best}"),
+        [best, ponder, ..] => println!("bestmove {best} ponder {ponder}"),
+    }
+}
+
+/// Count the positions `depth` plies deep for each move, and in total, printed like Stockfish does.
+fn print_perft(board: &Board, depth: u32) {
+    let start = Instant::now();
+    let counts = perft::divide(board, depth);
+    let elapsed = start.elapsed();
+    for (m, nodes) in &counts {
+        println!("{m}: {nodes}");
+    }
+    // At depth 0 the position itself is the only node, and there are no moves to list.
+    let nodes: u64 = match depth {
+        0 => 1,
+        _ => counts.iter().map(|(_, nodes)| nodes).sum(),
+    };
+    let nps = (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64;
+    println!();
+    println!("Nodes searched: {nodes}");
+    println!("Total time (ms) : {}", elapsed.as_millis());
+    println!("Nodes/second    : {nps}");
+}
 
 fn main() -> io::Result<()> {
-    let mut board = Board::blank();
-    let mut best_move: Option<Move> = None;
+    // None until a valid position is set, so a rejected 'position' is never searched.
+    let mut game: Option<Game> = None;
+    let mut search: Option<SearchThread> = None;
+    let mut options = Options::default();
+    let mut tt = Arc::new(TranspositionTable::new(options.hash));
 
     let mut input = String::new();
     let stdin = io::stdin();
     loop {
-        stdin.read_line(&mut input)?;
-        let mut parts = input.trim_end().split_whitespace();
+        input.clear();
+        if stdin.read_line(&mut input)? == 0 {
+            break;
+        }
+        let mut parts = input.split_whitespace();
+
+        // Anything but 'isready' and 'ponderhit' ends a running search first,
+        // so it never sees a position change under its feet.
+        if !matches!(parts.clone().next(), Some("isready" | "ponderhit") | None) {
+            if let Some(s) = search.take() {
+                s.stop();
+            }
+        }
+
         match parts.next() {
             Some("quit") => break,
             Some("uci") => {
                 println!("id name justok 1.0.0");
                 println!("id author herlufba");
+                for option in OPTIONS {
+                    println!("{option}");
+                }
                 println!("uciok");
             }
             Some("isready") => println!("readyok"),
+            Some("setoption") => {
+                let previous_hash = options.hash;
+                let result = Options::parse_setoption(parts)
+                    .and_then(|(name, value)| options.set(&name, value.as_deref()));
+                match result {
+                    // Keep the old table if there is no memory for the new one.
+                    Ok("Hash") => match TranspositionTable::try_new(options.hash) {
+                        Ok(table) => tt = Arc::new(table),
+                        Err(e) => {
+                            println!("info string {e}");
+                            options.hash = previous_hash;
+                        }
+                    },
+                    Ok("Clear Hash") => tt.clear(),
+                    Ok(_) => {}
+                    Err(e) => println!("info string {e}"),
+                }
+            }
+            Some("ucinewgame") => tt.clear(),
             Some("position") => {
+                // Forget the old position, whether or not the new one turns out valid.
+                game = None;
+
                 // Read either fen string or 'startpos' which is the standard position.
-                match parts.next() {
+                let mut new_board = match parts.next() {
                     Some("fen") => {
-                        let fen = parts.clone().collect::<Vec<_>>().join(" ");
-                        board = Board::from_fen(fen.as_str());
+                        // The FEN fields run until the optional 'moves' keyword.
+                        let fen = parts
+                            .by_ref()
+                            .take_while(|&p| p != "moves")
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        match Board::try_from_fen(fen.as_str()) {
+                            Ok(b) => b,
+                            Err(e) => {
+                                println!("info string Could not read position '{fen}': {e}");
+                                continue;
+                            }
+                        }
                     }
                     Some("startpos") => {
-                        board = Board::from_fen(
-                            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
-                        );
+                        Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
                     }
-                    _ => println!("Could not read position."),
+                    _ => {
+                        println!("Could not read position.");
+                        continue;
+                    }
+                };
+
+                // Fix up impossible castling rights and en pessant squares,
+                // but refuse to search positions that are broken beyond that.
+                for v in new_board.repair() {
+                    println!("info string Ignoring {v}");
                 }
+                let violations = new_board.validate();
+                if !violations.is_empty() {
+                    for v in violations {
+                        println!("info string Illegal position: {v}");
+                    }
+                    continue;
+                }
+                let mut new_game = Game::new(new_board);
 
-                // Read optional list of moves and apply them to the board.
-                if let Some("moves") = parts.next() {
-                    let moves: Vec<Move> = parts
-                        .enumerate()
-                        .map(|(idx, move_str)| Move::from_str(idx % 2 == 0, move_str))
-                        .collect();
-                    for m in moves {
-                        board.apply(m);
+                // Read optional list of moves and apply them to the game.
+                // For 'fen' the 'moves' keyword was already consumed above.
+                // A move that can't be read or isn't legal rejects the whole position.
+                if parts.clone().next() == Some("moves") {
+                    parts.next();
+                }
+                let mut valid = true;
+                for word in parts {
+                    let Some(m) = uci::parse_move(word) else {
+                        println!("info string Could not read move '{word}'");
+                        valid = false;
+                        break;
+                    };
+                    if !new_game.board().generate_moves().contains(&m) {
+                        println!("info string Illegal move '{word}'");
+                        valid = false;
+                        break;
                     }
+                    new_game.apply(m);
+                }
+                if valid {
+                    game = Some(new_game);
                 }
             }
-            Some("go") => match parts.next() {
-                Some(_) => {
-                    // TODO: implement all the sub commands like infinite, wtime and so on
-                    best_move = board.generate_moves().first().copied();
-                    // Just print the best move as soon as it is computed,
-                    // since theres no concept of time control yet.
-                    if let Some(m) = best_move {
-                        println!("info score cp 0");
-                        println!("bestmove {m}");
+            Some("go") => {
+                let Some(game) = &game else {
+                    println!("info string No position to search, send a valid 'position' first");
+                    continue;
+                };
+                let params = GoParams::parse(parts).unwrap_or_else(|e| {
+                    println!("info string {e}");
+                    GoParams {
+                        movetime: Some(FALLBACK_MOVETIME),
+                        ..GoParams::default()
                     }
+                });
+                if let Some(depth) = params.perft {
+                    print_perft(game.board(), depth);
+                    continue;
                 }
-                _ => println!("Unknown subcommand to 'go'"),
-            },
-            Some("stop") => {
-                if let Some(m) = best_move {
-                    println!("info score cp 0");
-                    println!("bestmove {m}");
+                let mut limits = params.limits(game.board().side_to_move(), options.move_overhead);
+                let (search_moves, warnings) = params.search_moves(game.board());
+                for warning in warnings {
+                    println!("info string {warning}");
                 }
+                limits.search_moves = search_moves;
+                search = Some(SearchThread::start(
+                    game.clone(),
+                    limits,
+                    params.infinite,
+                    params.ponder,
+                    options.search,
+                    Arc::clone(&tt),
+                ));
             }
+            Some("ponderhit") => {
+                if let Some(s) = &search {
+                    s.ponderhit();
+                }
+            }
+            // The running search was already stopped above.
+            Some("stop") => {}
             // NON-UCI DEBUGGING COMMANDS
             Some("board") => {
+                let Some(game) = &game else {
+                    println!("No position set.");
+                    continue;
+                };
+                let board = game.board();
                 println!("{}", board);
                 println!("fen: {}", board.to_fen());
+                println!("hash: {:016x}", board.hash());
                 println!(
                     "moves: {}",
                     board
@@ -81,7 +279,10 @@ fn main() -> io::Result<()> {
             Some(c) => println!("Unknown command '{c}'"),
             None => {}
         }
-        input.clear();
+    }
+
+    if let Some(s) = search {
+        s.stop();
     }
     Ok(())
 }
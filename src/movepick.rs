@@ -0,0 +1,208 @@
+use crate::{
+    board::{Board, GenType},
+    eval::piece_value,
+    Move, MoveList, Piece,
+};
+
+/// History scores stay within plus and minus this, so they keep adapting as the search goes on.
+const MAX_HISTORY: i32 = 16_384;
+
+/// Evasions that capture are tried before any quiet evasion.
+const CAPTURE_EVASION: i32 = 1_000_000;
+
+/// How often quiet moves have caused a beta cutoff, indexed by side to move, origin square and destination square.
+/// A quiet move that refutes one position tends to refute similar positions, so such moves are tried first.
+pub struct History {
+    table: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            table: Box::new([[[0; 64]; 64]; 2]),
+        }
+    }
+
+    /// Score of a move by the side to move.
+    pub fn get(&self, board: &Board, m: Move) -> i32 {
+        self.table[board.side_to_move() as usize][m.from as usize][m.to as usize]
+    }
+
+    /// Reward a move by the side to move with a positive bonus, or punish it with a negative one.
+    /// The closer a score is to the limit, the less it changes.
+    pub fn update(&mut self, board: &Board, m: Move, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+        let score = &mut self.table[board.side_to_move() as usize][m.from as usize][m.to as usize];
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+/// Score for ordering captures: Most Valuable Victim first, and of those the Least Valuable Attacker first.
+/// Promotions count as capturing the piece promoted to.
+pub fn mvv_lva(board: &Board, m: Move) -> i32 {
+    let victim = board.captured_piece(m).map_or(0, piece_value);
+    let promotion = m.promote_to.map_or(0, piece_value);
+    // Capping the king's value keeps the attacker from outweighing the difference between victims.
+    let attacker = board.at(m.from).map_or(0, piece_value).min(1000);
+    10 * (victim + promotion) - attacker
+}
+
+/// Is a move one of the [GenType::Captures]?
+fn is_noisy(board: &Board, m: Move) -> bool {
+    board.is_capture(m) || matches!(m.promote_to, Some(Piece::QueenWhite | Piece::QueenBlack))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    TtMove,
+    GenerateCaptures,
+    Captures,
+    Killers,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    GenerateEvasions,
+    Evasions,
+    Done,
+}
+
+/// Hands out the legal moves of a position one at a time, best guesses first,
+/// so a beta cutoff can happen before the rest of the moves are generated or sorted:
+///
+/// 1. The move from the transposition table.
+/// 2. Captures and queen promotions that don't lose material according to [Board::see], by [mvv_lva].
+/// 3. Killer moves, quiet moves that caused a cutoff at the same ply elsewhere in the tree.
+/// 4. The remaining quiet moves, by [History].
+/// 5. The captures that lose material.
+///
+/// In check the evasions are generated all at once, captures first.
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    killer_index: usize,
+    moves: MoveList,
+    scores: [i32; MoveList::CAPACITY],
+    index: usize,
+    bad_captures: MoveList,
+}
+
+impl MovePicker {
+    pub fn new(board: &Board, tt_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        Self {
+            stage: Stage::TtMove,
+            tt_move: tt_move.filter(|&m| board.is_legal(m)),
+            killers,
+            killer_index: 0,
+            moves: MoveList::new(),
+            scores: [0; MoveList::CAPACITY],
+            index: 0,
+            bad_captures: MoveList::new(),
+        }
+    }
+
+    /// The next move to try, or None when every legal move has been handed out.
+    /// The board must be in the same position as when the picker was created.
+    pub fn next(&mut self, board: &Board, history: &History) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = if board.is_in_check() {
+                        Stage::GenerateEvasions
+                    } else {
+                        Stage::GenerateCaptures
+                    };
+                    if self.tt_move.is_some() {
+                        return self.tt_move;
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.generate(board, GenType::Captures, |m| mvv_lva(board, m));
+                    self.stage = Stage::Captures;
+                }
+                Stage::Captures => match self.pick_best() {
+                    Some(m) if board.see(m) < 0 => self.bad_captures.push(m),
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    let Some(&killer) = self.killers.get(self.killer_index) else {
+                        self.stage = Stage::GenerateQuiets;
+                        continue;
+                    };
+                    self.killer_index += 1;
+                    if let Some(m) = killer.filter(|&m| {
+                        Some(m) != self.tt_move && !is_noisy(board, m) && board.is_legal(m)
+                    }) {
+                        return Some(m);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    self.generate(board, GenType::Quiets, |m| history.get(board, m));
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.pick_best() {
+                    Some(m) => return Some(m),
+                    None => {
+                        self.stage = Stage::BadCaptures;
+                        self.index = 0;
+                    }
+                },
+                Stage::BadCaptures => match self.bad_captures.get(self.index) {
+                    Some(&m) => {
+                        self.index += 1;
+                        return Some(m);
+                    }
+                    None => self.stage = Stage::Done,
+                },
+                Stage::GenerateEvasions => {
+                    self.generate(board, GenType::Evasions, |m| {
+                        if is_noisy(board, m) {
+                            CAPTURE_EVASION + mvv_lva(board, m)
+                        } else {
+                            history.get(board, m)
+                        }
+                    });
+                    self.stage = Stage::Evasions;
+                }
+                Stage::Evasions => match self.pick_best() {
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    /// Replace the moves with the legal moves of a kind, leaving out the moves already handed out.
+    fn generate(&mut self, board: &Board, gen: GenType, score: impl Fn(Move) -> i32) {
+        self.moves.clear();
+        board.generate(gen, &mut self.moves);
+        let tt_move = self.tt_move;
+        let killers = self.killers;
+        let already_tried =
+            |m: Move| Some(m) == tt_move || (gen == GenType::Quiets && killers.contains(&Some(m)));
+        self.moves.retain(|m| !already_tried(m));
+        for (i, &m) in self.moves.iter().enumerate() {
+            self.scores[i] = score(m);
+        }
+        self.index = 0;
+    }
+
+    /// Take the best scoring move that wasn't handed out yet.
+    /// Selecting one move at a time is cheaper than sorting, since most nodes only need the first few moves.
+    fn pick_best(&mut self) -> Option<Move> {
+        let remaining = self.index..self.moves.len();
+        let best = remaining.max_by_key(|&i| self.scores[i])?;
+        self.moves.swap(self.index, best);
+        self.scores.swap(self.index, best);
+        self.index += 1;
+        Some(self.moves[self.index - 1])
+    }
+}
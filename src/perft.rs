@@ -0,0 +1,56 @@
+//! Counting the positions reachable in a number of moves, to check move generation against known counts.
+
+use crate::{
+    board::{Board, GenType},
+    Move, MoveList,
+};
+
+/// Count the leaf nodes of the tree of legal moves `depth` plies deep.
+/// The counts of many positions are known, so a wrong count points to a bug in move generation.
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    let mut board = *board;
+    count(&mut board, depth)
+}
+
+/// Like [perft], but split up by the first move, in the order the moves are generated.
+/// Comparing these counts with another engine's narrows a wrong count down to a move.
+/// The first move counts towards `depth`, so at depth 0 there are no moves to split by.
+pub fn divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut board = *board;
+    let mut moves = MoveList::new();
+    board.generate(GenType::All, &mut moves);
+    moves
+        .iter()
+        .map(|&m| {
+            let undo = board.make_move(m);
+            let nodes = count(&mut board, depth - 1);
+            board.unmake_move(m, undo);
+            (m, nodes)
+        })
+        .collect()
+}
+
+fn count(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = MoveList::new();
+    board.generate(GenType::All, &mut moves);
+    // Every legal move leads to exactly one leaf, so there is no need to make them.
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .map(|&m| {
+            let undo = board.make_move(m);
+            let nodes = count(board, depth - 1);
+            board.unmake_move(m, undo);
+            nodes
+        })
+        .sum()
+}
@@ -0,0 +1,823 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, GenType},
+    eval::{evaluate, piece_value},
+    game::Game,
+    movepick::{mvv_lva, History, MovePicker},
+    time::TimeLimits,
+    tt::{Bound, TranspositionTable},
+    uci::format_score,
+    Move, MoveList,
+};
+
+/// Score for being checkmated at the root. Being mated `n` plies from the root scores `-MATE + n`,
+/// so quicker mates are preferred, and slower mates are preferred when being mated.
+pub const MATE: i32 = 32_000;
+
+/// Larger than any score a search can return.
+pub const INFINITY: i32 = 32_001;
+
+/// Score of a drawn position.
+pub const DRAW: i32 = 0;
+
+/// The deepest the search will ever go.
+pub const MAX_DEPTH: u32 = 64;
+
+/// The furthest from the root the search will ever look, counting extensions and the quiescence search.
+pub const MAX_PLY: u32 = 2 * MAX_DEPTH;
+
+/// Scores beyond this, positive or negative, are mates.
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+/// A capture in the quiescence search is skipped if even winning this much more than the captured piece
+/// can't raise the score to alpha.
+const DELTA_MARGIN: i32 = 200;
+
+/// How many nodes to search between checking whether the search should stop.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Iterations from this depth on search a window this wide on either side of the previous score first.
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 40;
+
+/// Null moves are only tried with at least this much depth left.
+const NULL_MOVE_MIN_DEPTH: u32 = 3;
+
+/// Late moves are only reduced with at least this much depth left,
+const LMR_MIN_DEPTH: u32 = 3;
+/// and after this many moves have been searched at full depth.
+const LMR_FULL_DEPTH_MOVES: u32 = 3;
+
+/// How to search, as configured through UCI options.
+/// The search techniques can be turned off, so each one can be tested against the search without it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Number of threads searching the same position, sharing the transposition table.
+    pub threads: usize,
+    /// Number of best lines to find, each with its own first move.
+    pub multi_pv: usize,
+    /// Principal variation search: search every move but the first with a zero window,
+    /// only re-searching with the full window when a move turns out better than the first.
+    pub pvs: bool,
+    /// Null move pruning: if passing the turn still scores above beta, so will a real move.
+    pub null_move: bool,
+    /// Late move reductions: search quiet moves ordered late with less depth,
+    /// re-searching at full depth if they beat alpha anyway.
+    pub lmr: bool,
+    /// Search one ply deeper when in check.
+    pub check_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            multi_pv: 1,
+            pvs: true,
+            null_move: true,
+            lmr: true,
+            check_extensions: true,
+        }
+    }
+}
+
+/// Constraints on how long a search may run. All limits are optional,
+/// and the default is to search until [MAX_DEPTH].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Search this many plies only.
+    pub depth: Option<u32>,
+    /// Stop after visiting this many nodes.
+    pub nodes: Option<u64>,
+    /// Stop once a mate in this many moves is found.
+    pub mate: Option<u32>,
+    /// Stop when running out of time.
+    pub time: Option<TimeLimits>,
+    /// Only search these moves at the root. Empty means every move.
+    /// The moves must be legal.
+    pub search_moves: Vec<Move>,
+}
+
+impl Limits {
+    /// Search this many plies and nothing more.
+    pub fn to_depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+}
+
+/// The outcome of searching a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The best move found, or None if there are no legal moves.
+    pub best_move: Option<Move>,
+    /// Score in centipawns from the point of view of the side to move.
+    pub score: i32,
+    /// The principal variation, the line of play expected from both sides. Starts with the best move.
+    pub pv: Vec<Move>,
+    /// The best lines with different first moves, best first, as many as [SearchOptions::multi_pv] asks for.
+    /// The first line is the principal variation.
+    pub lines: Vec<Line>,
+    /// The deepest iteration that completed, 0 if the search was stopped before any did.
+    pub depth: u32,
+    /// Number of positions visited in total, by all threads.
+    pub nodes: u64,
+}
+
+/// Flags set from outside a running search, usually by the thread reading UCI commands.
+#[derive(Debug, Default)]
+pub struct Signals {
+    /// Stop searching as soon as possible.
+    pub stop: AtomicBool,
+    /// The search is thinking on the opponent's time, so its time limits don't apply yet.
+    /// Once cleared, the opponent played the expected move, and the clock starts running.
+    pub ponder: AtomicBool,
+}
+
+/// A line of play from the root, with its score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub score: i32,
+    pub pv: Vec<Move>,
+}
+
+/// Search a game's current position with iterative deepening until a limit is reached.
+/// Prints a UCI `info` line after every completed iteration.
+/// Uses a fresh transposition table of the default size and the default [SearchOptions].
+pub fn search(game: &Game, limits: &Limits) -> SearchResult {
+    search_until(
+        game,
+        limits,
+        &SearchOptions::default(),
+        &TranspositionTable::default(),
+        &Signals::default(),
+    )
+}
+
+/// Like [search], but reuses the results in `tt` from earlier searches,
+/// and is controlled through `signals`, so a search running on another thread can be interrupted.
+/// A search stopped before its first iteration completes still plays a legal move, if there is one.
+///
+/// With more than one thread, helper threads search the same position (Lazy SMP).
+/// They only share the transposition table, where they leave results that speed up the other threads,
+/// and they search at different depths, so they don't all work on the same part of the tree.
+/// Once the main thread is done, the helpers are stopped and the threads vote on the move to play.
+pub fn search_until(
+    game: &Game,
+    limits: &Limits,
+    options: &SearchOptions,
+    tt: &TranspositionTable,
+    signals: &Signals,
+) -> SearchResult {
+    tt.new_search();
+    let total_nodes = AtomicU64::new(0);
+    let mut main = Searcher::new(game, limits.clone(), *options, tt, signals, &total_nodes);
+    if options.threads <= 1 {
+        return main.iterative_deepening();
+    }
+
+    // Helpers search until the main thread is done, only the root moves are restricted the same way.
+    let helper_limits = Limits {
+        search_moves: limits.search_moves.clone(),
+        ..Limits::default()
+    };
+    let helper_signals = Signals::default();
+    thread::scope(|scope| {
+        let helpers: Vec<_> = (1..options.threads)
+            .map(|id| {
+                let mut helper = Searcher::new(
+                    game,
+                    helper_limits.clone(),
+                    *options,
+                    tt,
+                    &helper_signals,
+                    &total_nodes,
+                );
+                helper.thread_id = id;
+                scope.spawn(move || helper.iterative_deepening())
+            })
+            .collect();
+        let mut results = vec![main.iterative_deepening()];
+        helper_signals.stop.store(true, Ordering::Relaxed);
+        results.extend(
+            helpers
+                .into_iter()
+                .map(|h| h.join().expect("search thread panicked")),
+        );
+
+        let nodes = results.iter().map(|r| r.nodes).sum();
+        // The lines are ranked within a single thread, so don't mix in other threads' moves.
+        let best = if options.multi_pv > 1 {
+            0
+        } else {
+            vote(&results)
+        };
+        let mut result = results.swap_remove(best);
+        if best != 0 {
+            main.print_info(result.depth, 0, result.score, Bound::Exact, &result.pv);
+        }
+        result.nodes = nodes;
+        result
+    })
+}
+
+/// Pick the result of the thread whose move the threads agree on the most.
+/// Each thread votes for its best move, with a weight growing with the depth it reached
+/// and how much better its score is than the worst score of any thread.
+/// A forced mate overrules the vote, since it is proven.
+/// Only threads that got at least as deep as the main thread, the first result, can be picked.
+fn vote(results: &[SearchResult]) -> usize {
+    let worst = results.iter().map(|r| r.score).min().unwrap_or(0);
+    let mut votes = HashMap::new();
+    for result in results {
+        *votes.entry(result.best_move).or_insert(0) +=
+            (result.score - worst + 14) as i64 * result.depth as i64;
+    }
+    (0..results.len())
+        .filter(|&i| results[i].depth >= results[0].depth)
+        .max_by_key(|&i| {
+            let result = &results[i];
+            let is_mate = result.score >= MATE_BOUND;
+            (
+                is_mate.then_some(result.score),
+                votes[&result.best_move],
+                result.depth,
+                // Ties go to the main thread.
+                std::cmp::Reverse(i),
+            )
+        })
+        .unwrap_or(0)
+}
+
+/// State of a running search.
+struct Searcher<'a> {
+    board: Board,
+    /// Hashes of every position before the current one, both from the game and the search.
+    history: Vec<u64>,
+    nodes: u64,
+    /// Nodes visited by all threads, to report on the search as a whole.
+    /// Each thread adds its nodes once in a while, so the threads don't contend for it on every node.
+    total_nodes: &'a AtomicU64,
+    /// The part of `nodes` already added to `total_nodes`.
+    counted_nodes: u64,
+    limits: Limits,
+    options: SearchOptions,
+    /// 0 for the main thread, which reports on the search. Helper threads search silently.
+    thread_id: usize,
+    start: Instant,
+    /// When the clock started running, which is later than the start when pondering.
+    clock_start: Instant,
+    /// Thinking on the opponent's time, see [Signals::ponder].
+    pondering: bool,
+    /// Set when a limit is hit in the middle of an iteration. The iteration's result is then thrown away.
+    stopped: bool,
+    /// Depth of the last iteration that finished.
+    completed_depth: u32,
+    tt: &'a TranspositionTable,
+    signals: &'a Signals,
+    /// Root moves that are left out of the search, because they start lines that were already found.
+    excluded: Vec<Move>,
+    /// Two quiet moves per ply that recently caused a beta cutoff.
+    killers: Vec<[Option<Move>; 2]>,
+    quiet_history: History,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        game: &Game,
+        limits: Limits,
+        options: SearchOptions,
+        tt: &'a TranspositionTable,
+        signals: &'a Signals,
+        total_nodes: &'a AtomicU64,
+    ) -> Self {
+        Self {
+            board: *game.board(),
+            history: game.history().to_vec(),
+            nodes: 0,
+            total_nodes,
+            counted_nodes: 0,
+            limits,
+            options,
+            thread_id: 0,
+            start: Instant::now(),
+            clock_start: Instant::now(),
+            pondering: signals.ponder.load(Ordering::Relaxed),
+            stopped: false,
+            completed_depth: 0,
+            tt,
+            signals,
+            excluded: Vec::new(),
+            killers: vec![[None; 2]; MAX_PLY as usize],
+            quiet_history: History::new(),
+        }
+    }
+
+    /// Search with increasing depth, so each iteration refines the result of the last.
+    /// When stopped before the first iteration completes, the best move is a guess, see [search_until].
+    fn iterative_deepening(&mut self) -> SearchResult {
+        let max_depth = match (self.limits.depth, self.limits.mate) {
+            (Some(depth), _) => depth,
+            // A mate in n moves takes 2n - 1 plies.
+            (None, Some(mate)) => mate.saturating_mul(2),
+            (None, None) => MAX_DEPTH,
+        }
+        .clamp(1, MAX_DEPTH);
+        // Every line needs a move of its own.
+        let root_moves = match self.limits.search_moves.len() {
+            0 => self.board.generate_moves().len(),
+            n => n,
+        };
+        let multi_pv = self.options.multi_pv.min(root_moves).max(1);
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            pv: Vec::new(),
+            lines: Vec::new(),
+            depth: 0,
+            nodes: 0,
+        };
+
+        for depth in 1..=max_depth {
+            // Half the helpers search a ply deeper, so the threads spread out over more of the tree.
+            let depth = (depth + self.thread_id as u32 % 2).min(MAX_DEPTH);
+            // Find the lines one at a time, each time leaving out the first moves of the lines already found.
+            let mut lines = Vec::new();
+            self.excluded.clear();
+            for index in 0..multi_pv {
+                let previous = result.lines.get(index).map(|line| line.score);
+                let mut pv = Vec::new();
+                let score = self.aspiration_search(depth, index, previous, &mut pv);
+                if self.stopped {
+                    break;
+                }
+                self.excluded.extend(pv.first());
+                lines.push(Line { score, pv });
+            }
+            if self.stopped {
+                break;
+            }
+            // A line found later may still turn out better, as the lines are searched separately.
+            lines.sort_by_key(|line| -line.score);
+            result = SearchResult {
+                best_move: lines[0].pv.first().copied(),
+                score: lines[0].score,
+                pv: lines[0].pv.clone(),
+                lines,
+                depth,
+                nodes: self.nodes,
+            };
+            self.completed_depth = depth;
+            for (index, line) in result.lines.iter().enumerate() {
+                self.print_info(depth, index, line.score, Bound::Exact, &line.pv);
+            }
+
+            // No need to search deeper when there is nothing to choose between.
+            if result.best_move.is_none() {
+                break;
+            }
+            if let Some(mate) = self.limits.mate {
+                let plies = mate.saturating_mul(2).saturating_sub(1).min(MAX_PLY);
+                if result.score >= MATE - plies as i32 {
+                    break;
+                }
+            }
+            // Another iteration takes longer than all the previous ones,
+            // so don't start one that is unlikely to finish.
+            let time = self.limits.time;
+            if time.is_some_and(|time| self.clock_elapsed().is_some_and(|e| e >= time.soft)) {
+                break;
+            }
+        }
+
+        // Stopped before the first iteration finished, so there is no line to play.
+        // Fall back to the move the table remembers for this position, or else the first legal move.
+        if self.completed_depth == 0 && self.stopped {
+            self.excluded.clear();
+            let legal: Vec<Move> = self
+                .board
+                .generate_moves()
+                .into_iter()
+                .filter(|&m| self.is_root_move(m))
+                .collect();
+            let tt_move = self
+                .tt
+                .probe(self.board.hash(), 0)
+                .and_then(|entry| entry.best_move)
+                .filter(|m| legal.contains(m));
+            result.best_move = tt_move.or(legal.first().copied());
+            result.pv = result.best_move.into_iter().collect();
+            result.lines = vec![Line {
+                score: 0,
+                pv: result.pv.clone(),
+            }];
+        }
+
+        // Count the nodes of an unfinished last iteration too.
+        result.nodes = self.nodes;
+        self.count_nodes();
+        result
+    }
+
+    /// Search the root with a narrow window around the score of the line in the previous iteration,
+    /// which prunes more of the tree as long as the score doesn't change much.
+    /// When the score falls outside the window, the window is widened on that side and the root searched again.
+    fn aspiration_search(
+        &mut self,
+        depth: u32,
+        index: usize,
+        previous: Option<i32>,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match previous {
+            Some(previous) if depth >= ASPIRATION_MIN_DEPTH && previous.abs() < MATE_BOUND => {
+                (previous - delta, previous + delta)
+            }
+            _ => (-INFINITY, INFINITY),
+        };
+        loop {
+            let score = self.negamax(depth, 0, alpha, beta, pv);
+            if self.stopped {
+                return score;
+            }
+            if score <= alpha {
+                self.print_info(depth, index, score, Bound::Upper, pv);
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                self.print_info(depth, index, score, Bound::Lower, pv);
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta *= 2;
+        }
+    }
+
+    /// Print a UCI `info` line about the search so far, for the line with the given index.
+    /// A score that fell outside the aspiration window is only a bound, and marked as such.
+    fn print_info(&self, depth: u32, index: usize, score: i32, bound: Bound, pv: &[Move]) {
+        if self.thread_id != 0 {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        let nodes = self.total_nodes.load(Ordering::Relaxed) + self.nodes - self.counted_nodes;
+        let nps = (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64;
+        let multi_pv = if self.options.multi_pv > 1 {
+            format!(" multipv {}", index + 1)
+        } else {
+            String::new()
+        };
+        let bound = match bound {
+            Bound::Exact => "",
+            Bound::Lower => " lowerbound",
+            Bound::Upper => " upperbound",
+        };
+        let mut line = format!(
+            "info depth {depth}{multi_pv} score {}{bound} nodes {nodes} nps {nps} hashfull {} time {}",
+            format_score(score),
+            self.tt.hashfull(),
+            elapsed.as_millis(),
+        );
+        if !pv.is_empty() {
+            line += " pv";
+            for m in pv {
+                line += &format!(" {m}");
+            }
+        }
+        println!("{line}");
+    }
+
+    /// Negamax search with alpha-beta pruning.
+    /// Scores are from the point of view of the side to move, so a child's score is negated.
+    /// Moves scoring below alpha are already refuted, moves scoring above beta will be avoided by the opponent.
+    /// Fills `pv` with the best line found from this position.
+    /// Results are stored in the transposition table, and positions searched deep enough before are cut short.
+    fn negamax(
+        &mut self,
+        mut depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        let in_check = self.board.is_in_check();
+        // Checks are forcing, so don't let the horizon cut a sequence of them short.
+        if in_check && self.options.check_extensions && ply < MAX_PLY {
+            depth += 1;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+
+        self.check_limits();
+        if self.stopped {
+            return 0;
+        }
+
+        if ply > 0 && self.is_draw() {
+            return DRAW;
+        }
+
+        let entry = self.tt.probe(self.board.hash(), ply);
+        // The root always searches, so there is a best move and a principal variation.
+        if let Some(entry) = entry.filter(|_| ply > 0) {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if entry.depth >= depth && usable {
+                return entry.score;
+            }
+        }
+
+        // Only nodes searched with a zero window may be pruned, the others are needed for the principal variation.
+        let is_pv = beta - alpha > 1;
+        if self.options.null_move
+            && !is_pv
+            && !in_check
+            && depth >= NULL_MOVE_MIN_DEPTH
+            // Without pieces, passing may well be the best move there is (zugzwang), so the null move proves nothing.
+            && self.board.has_non_pawn_material(self.board.side_to_move())
+            && evaluate(&self.board) >= beta
+        {
+            let reduction = 3 + depth / 6;
+            self.history.push(self.board.hash());
+            let undo = self.board.make_null_move();
+            let score = -self.negamax(
+                depth.saturating_sub(1 + reduction),
+                ply + 1,
+                -beta,
+                -beta + 1,
+                &mut Vec::new(),
+            );
+            self.board.unmake_null_move(undo);
+            self.history.pop();
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                // A mate found after passing isn't proven, so don't return it.
+                return if score >= MATE_BOUND { beta } else { score };
+            }
+        }
+
+        let killers = self.killers.get(ply as usize).copied().unwrap_or_default();
+        let mut picker = MovePicker::new(&self.board, entry.and_then(|e| e.best_move), killers);
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        let mut moves_searched = 0;
+        let mut quiets_tried = MoveList::new();
+        while let Some(m) = picker.next(&self.board, &self.quiet_history) {
+            if ply == 0 && !self.is_root_move(m) {
+                continue;
+            }
+            moves_searched += 1;
+            let is_quiet = !self.board.is_capture(m) && m.promote_to.is_none();
+            let history = self.quiet_history.get(&self.board, m);
+            self.history.push(self.board.hash());
+            let undo = self.board.make_move(m);
+
+            let score = if moves_searched == 1 {
+                -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+            } else {
+                // Later moves are expected to fail low, which a zero window proves more cheaply.
+                let zero_window = if self.options.pvs { -alpha - 1 } else { -beta };
+                let reduction = if self.options.lmr
+                    && is_quiet
+                    && !in_check
+                    && depth >= LMR_MIN_DEPTH
+                    && moves_searched > LMR_FULL_DEPTH_MOVES
+                    && !self.board.is_in_check()
+                {
+                    late_move_reduction(depth, moves_searched, history)
+                } else {
+                    0
+                };
+
+                let mut score = -self.negamax(
+                    depth - 1 - reduction,
+                    ply + 1,
+                    zero_window,
+                    -alpha,
+                    &mut child_pv,
+                );
+                if reduction > 0 && score > alpha {
+                    score = -self.negamax(depth - 1, ply + 1, zero_window, -alpha, &mut child_pv);
+                }
+                if self.options.pvs && score > alpha && score < beta {
+                    score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+                }
+                score
+            };
+
+            self.board.unmake_move(m, undo);
+            self.history.pop();
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(m);
+                pv.clear();
+                pv.push(m);
+                pv.extend_from_slice(&child_pv);
+            }
+            if alpha >= beta {
+                if is_quiet {
+                    self.reward_quiet(m, ply, depth, &quiets_tried);
+                }
+                break;
+            }
+            if is_quiet {
+                quiets_tried.push(m);
+            }
+        }
+
+        if moves_searched == 0 {
+            return if in_check { -MATE + ply as i32 } else { DRAW };
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        // With moves left out, the root's score isn't the score of the position.
+        if ply > 0 || (self.excluded.is_empty() && self.limits.search_moves.is_empty()) {
+            self.tt
+                .store(self.board.hash(), ply, depth, bound, best, best_move);
+        }
+        best
+    }
+
+    /// Search captures until the position is quiet, so a position isn't evaluated in the middle of an exchange.
+    /// The side to move may also stand pat, declining every capture and taking the static evaluation,
+    /// except when in check, where every evasion is searched instead.
+    fn quiescence(&mut self, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if ply >= MAX_PLY {
+            return evaluate(&self.board);
+        }
+
+        self.check_limits();
+        if self.stopped {
+            return 0;
+        }
+
+        if self.is_draw() {
+            return DRAW;
+        }
+
+        let in_check = self.board.is_in_check();
+        let mut best = -INFINITY;
+        let mut stand_pat = -INFINITY;
+        let mut moves = MoveList::new();
+        if in_check {
+            self.board.generate(GenType::Evasions, &mut moves);
+            if moves.is_empty() {
+                return -MATE + ply as i32;
+            }
+        } else {
+            stand_pat = evaluate(&self.board);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best = stand_pat;
+            self.board.generate(GenType::Captures, &mut moves);
+            // Try winning the most valuable piece with the least valuable one first,
+            // those captures are the most likely to cause a cutoff.
+            moves.sort_by_key(|&m| -mvv_lva(&self.board, m));
+        }
+
+        for &m in &moves {
+            // Delta pruning: skip captures that can't possibly bring the score back up to alpha.
+            let captured = self.board.captured_piece(m).map_or(0, piece_value);
+            if !in_check && m.promote_to.is_none() && stand_pat + captured + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            // Captures that lose material won't help either.
+            if !in_check && self.board.see(m) < 0 {
+                continue;
+            }
+
+            self.history.push(self.board.hash());
+            let undo = self.board.make_move(m);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.board.unmake_move(m, undo);
+            self.history.pop();
+            if self.stopped {
+                return 0;
+            }
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// Should a move be searched at the root?
+    /// Not if the GUI left it out, or it starts one of the lines already found.
+    fn is_root_move(&self, m: Move) -> bool {
+        (self.limits.search_moves.is_empty() || self.limits.search_moves.contains(&m))
+            && !self.excluded.contains(&m)
+    }
+
+    /// A quiet move caused a beta cutoff, so try it early in similar positions.
+    /// It becomes a killer move at this ply, and gains history while the quiet moves tried before it lose some.
+    fn reward_quiet(&mut self, m: Move, ply: u32, depth: u32, quiets_tried: &[Move]) {
+        if let Some(killers) = self.killers.get_mut(ply as usize) {
+            if killers[0] != Some(m) {
+                killers[1] = killers[0];
+                killers[0] = Some(m);
+            }
+        }
+        let bonus = (depth * depth) as i32;
+        self.quiet_history.update(&self.board, m, bonus);
+        for &tried in quiets_tried {
+            self.quiet_history.update(&self.board, tried, -bonus);
+        }
+    }
+
+    /// Stop the search if it has run out of nodes or time, or has been told to stop.
+    fn check_limits(&mut self) {
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.count_nodes();
+        }
+        let told_to_stop = self.signals.stop.load(Ordering::Relaxed);
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        // Reading the clock is slow compared to searching a node, so only do it once in a while.
+        let time = self.limits.time;
+        let out_of_time = self.nodes.is_multiple_of(CHECK_INTERVAL)
+            && time.is_some_and(|time| self.clock_elapsed().is_some_and(|e| e >= time.hard));
+        self.stopped |= told_to_stop || out_of_nodes || out_of_time;
+    }
+
+    /// Add the nodes visited since the last time to the count of all threads.
+    fn count_nodes(&mut self) {
+        self.total_nodes
+            .fetch_add(self.nodes - self.counted_nodes, Ordering::Relaxed);
+        self.counted_nodes = self.nodes;
+    }
+
+    /// Time spent on the clock, or None while pondering, as the clock isn't running yet.
+    fn clock_elapsed(&mut self) -> Option<Duration> {
+        if self.pondering {
+            if self.signals.ponder.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.pondering = false;
+            self.clock_start = Instant::now();
+        }
+        Some(self.clock_start.elapsed())
+    }
+
+    /// Is the current position a draw by the fifty move rule, insufficient material or repetition?
+    /// Within the search a single repetition counts as a draw,
+    /// since whatever was possible the first time is possible again.
+    fn is_draw(&self) -> bool {
+        if self.board.half_move_clock() >= 100 || self.board.is_insufficient_material() {
+            return true;
+        }
+        let hash = self.board.hash();
+        self.history
+            .iter()
+            .rev()
+            .take(self.board.half_move_clock() as usize)
+            .any(|&h| h == hash)
+    }
+}
+
+/// How many plies less to search a late quiet move with.
+/// The later a move comes in the ordering and the more depth is left, the more it is reduced,
+/// while moves with a good history are reduced less. Always leaves at least one ply.
+fn late_move_reduction(depth: u32, moves_searched: u32, history: i32) -> u32 {
+    let base = 0.75 + (depth as f64).ln() * (moves_searched as f64).ln() / 2.25;
+    let reduction = base as i32 - history / 8192;
+    reduction.clamp(0, depth as i32 - 2) as u32
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+/// Time kept in reserve for communication with the GUI, so the engine doesn't lose on time.
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+
+/// How many moves to budget for when the GUI doesn't say how many moves are left until the next time control.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// How long a search may think about a move.
+/// The search won't start another iteration after the soft limit,
+/// and stops as soon as possible after the hard limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl TimeLimits {
+    /// Think for exactly a certain amount of time, like `go movetime`.
+    pub fn fixed(time: Duration, overhead: Duration) -> Self {
+        let time = time.saturating_sub(overhead).max(Duration::from_millis(1));
+        Self {
+            soft: time,
+            hard: time,
+        }
+    }
+
+    /// Allot time for a move from the time left on the clock.
+    /// Spreads the time evenly over the moves left, and uses most of the increment too.
+    /// The hard limit allows going over the budget when an iteration is expensive,
+    /// but never uses more than a third of the clock.
+    pub fn from_clock(
+        time_left: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+        overhead: Duration,
+    ) -> Self {
+        let moves_to_go = moves_to_go
+            .filter(|&m| m > 0)
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .min(DEFAULT_MOVES_TO_GO);
+        let available = time_left.saturating_sub(overhead);
+        let budget = available / moves_to_go + increment * 3 / 4;
+
+        let hard = (budget * 4)
+            .min(available / 3 + increment / 2)
+            .min(available)
+            .max(Duration::from_millis(1));
+        let soft = budget.min(hard);
+        Self { soft, hard }
+    }
+}
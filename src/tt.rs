@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::{search::MATE_BOUND, Color, Move, Piece};
+
+/// Size of the transposition table in MB when the GUI doesn't set one.
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// Largest size of the transposition table in MB the GUI may ask for.
+pub const MAX_HASH_MB: usize = 4096;
+
+/// Number of entries sharing a slot in the table. A new entry replaces the least valuable one in its bucket.
+const BUCKET_SIZE: usize = 4;
+
+/// How a stored score relates to the true score of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact = 1,
+    /// The search failed high, so the true score is at least this.
+    Lower = 2,
+    /// The search failed low, so the true score is at most this.
+    Upper = 3,
+}
+
+/// What the table remembers about a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    /// Remaining depth of the search that produced this entry.
+    pub depth: u32,
+    pub bound: Bound,
+    /// Score from the point of view of the side to move, with mates counted from the root of the probing search.
+    pub score: i32,
+    /// The best move found, if any move raised alpha.
+    pub best_move: Option<Move>,
+}
+
+/// A fixed-size table of search results keyed by the hash of a position, shared by all search threads.
+/// Each entry is packed in a single atomic word, so entries are never torn even without locking:
+///
+/// | bits  | content                                  |
+/// |-------|------------------------------------------|
+/// | 0-15  | lowest 16 bits of the position's hash    |
+/// | 16-31 | best move                                |
+/// | 32-47 | score                                    |
+/// | 48-55 | depth                                    |
+/// | 56-57 | bound, where 0 marks an empty entry      |
+/// | 58-63 | age of the search that stored the entry  |
+///
+/// The bucket is picked by the highest bits of the hash, so the stored bits verify the rest.
+pub struct TranspositionTable {
+    entries: Vec<AtomicU64>,
+    /// Incremented for every new search, so entries from old searches are replaced first.
+    age: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// Create an empty table taking up `mb` megabytes, at least one bucket.
+    /// Panics if the memory can't be allocated, see [TranspositionTable::try_new].
+    pub fn new(mb: usize) -> Self {
+        Self::try_new(mb).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [TranspositionTable::new], but returns an error if the memory can't be allocated.
+    pub fn try_new(mb: usize) -> Result<Self, String> {
+        let buckets = (mb.saturating_mul(1024 * 1024) / (8 * BUCKET_SIZE)).max(1);
+        let mut entries = Vec::new();
+        entries
+            .try_reserve_exact(buckets * BUCKET_SIZE)
+            .map_err(|_| format!("Could not allocate {mb} MB for the hash table"))?;
+        entries.extend((0..buckets * BUCKET_SIZE).map(|_| AtomicU64::new(0)));
+        Ok(Self {
+            entries,
+            age: AtomicU8::new(0),
+        })
+    }
+
+    /// Forget everything, as if the table was just created.
+    pub fn clear(&self) {
+        for entry in &self.entries {
+            entry.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Mark the start of a new search, making entries from earlier searches less valuable.
+    pub fn new_search(&self) {
+        let age = self.age.load(Ordering::Relaxed);
+        self.age.store((age + 1) & 0x3F, Ordering::Relaxed);
+    }
+
+    /// Look up a position `ply` plies from the root of the search.
+    pub fn probe(&self, hash: u64, ply: u32) -> Option<Entry> {
+        self.bucket(hash)
+            .iter()
+            .map(|e| e.load(Ordering::Relaxed))
+            .find(|&data| bound(data).is_some() && data as u16 == hash as u16)
+            .map(|data| Entry {
+                depth: (data >> 48) as u8 as u32,
+                bound: bound(data).unwrap(),
+                score: score_from_tt((data >> 32) as u16 as i16 as i32, ply),
+                best_move: decode_move((data >> 16) as u16),
+            })
+    }
+
+    /// Remember the result of searching a position `ply` plies from the root.
+    /// Replaces the entry for the same position if there is one,
+    /// otherwise the shallowest entry in the bucket, preferring entries from earlier searches.
+    pub fn store(
+        &self,
+        hash: u64,
+        ply: u32,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+    ) {
+        let age = self.age.load(Ordering::Relaxed);
+        let bucket = self.bucket(hash);
+        let slot = bucket
+            .iter()
+            .find(|e| e.load(Ordering::Relaxed) as u16 == hash as u16)
+            .unwrap_or_else(|| {
+                bucket
+                    .iter()
+                    .min_by_key(|e| self.worth(e.load(Ordering::Relaxed), age))
+                    .unwrap()
+            });
+
+        // Keep the old move if there is no new one, it is still the best guess.
+        let old = slot.load(Ordering::Relaxed);
+        let move_bits = match best_move {
+            Some(m) => encode_move(m),
+            None if old as u16 == hash as u16 => (old >> 16) as u16,
+            None => 0,
+        };
+        let data = hash as u16 as u64
+            | (move_bits as u64) << 16
+            | (score_to_tt(score, ply) as i16 as u16 as u64) << 32
+            | (depth.min(u8::MAX as u32) as u64) << 48
+            | (bound as u64) << 56
+            | (age as u64) << 58;
+        slot.store(data, Ordering::Relaxed);
+    }
+
+    /// How full the table is in permille, counting only entries from the current search.
+    /// Samples the first thousand entries, like the UCI `hashfull` info asks.
+    pub fn hashfull(&self) -> u32 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample
+            .iter()
+            .map(|e| e.load(Ordering::Relaxed))
+            .filter(|&data| bound(data).is_some() && (data >> 58) as u8 == age)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn bucket(&self, hash: u64) -> &[AtomicU64] {
+        let buckets = self.entries.len() / BUCKET_SIZE;
+        let index = ((hash as u128 * buckets as u128) >> 64) as usize;
+        &self.entries[index * BUCKET_SIZE..(index + 1) * BUCKET_SIZE]
+    }
+
+    /// How valuable an entry is to keep. Empty entries are worth nothing,
+    /// and every search since an entry was stored counts as much as 8 plies of depth.
+    fn worth(&self, data: u64, age: u8) -> i32 {
+        if bound(data).is_none() {
+            return i32::MIN;
+        }
+        let depth = (data >> 48) as u8 as i32;
+        let searches_since = (age.wrapping_sub((data >> 58) as u8) & 0x3F) as i32;
+        depth - 8 * searches_since
+    }
+}
+
+fn bound(data: u64) -> Option<Bound> {
+    match (data >> 56) & 0b11 {
+        1 => Some(Bound::Exact),
+        2 => Some(Bound::Lower),
+        3 => Some(Bound::Upper),
+        _ => None,
+    }
+}
+
+/// Mate scores are counted from the root of the search, but the same position can be reached at any ply.
+/// Count them from the stored position instead, and back again when probing.
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Pack a move in 16 bits: 6 for each square, and 4 for the promoted piece.
+/// No move travels from a1 to a1, so 0 means no move.
+fn encode_move(m: Move) -> u16 {
+    m.from as u16 | (m.to as u16) << 6 | (m.promote_to.map_or(0, |p| p as u16)) << 12
+}
+
+fn decode_move(bits: u16) -> Option<Move> {
+    if bits == 0 {
+        return None;
+    }
+    let promote_to = (bits >> 12) as u8;
+    Some(Move {
+        from: (bits & 0x3F) as u8,
+        to: (bits >> 6 & 0x3F) as u8,
+        promote_to: [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| color.pieces())
+            .find(|&p: &Piece| p as u8 == promote_to),
+    })
+}
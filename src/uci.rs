@@ -0,0 +1,409 @@
+use std::time::Duration;
+
+use crate::{
+    board::Board,
+    search::{Limits, SearchOptions, MATE, MATE_BOUND, MAX_DEPTH},
+    time::{TimeLimits, DEFAULT_MOVE_OVERHEAD},
+    try_square_from_algebraic,
+    tt::{DEFAULT_HASH_MB, MAX_HASH_MB},
+    Color, Move,
+};
+
+/// The keywords of a `go` command. A list of moves after `searchmoves` runs until the next one.
+const GO_KEYWORDS: &[&str] = &[
+    "searchmoves",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+    "ponder",
+    "perft",
+];
+
+/// The parameters of a UCI `go` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoParams {
+    /// Time left on white's clock.
+    pub wtime: Option<Duration>,
+    /// Time left on black's clock.
+    pub btime: Option<Duration>,
+    /// White's increment per move.
+    pub winc: Option<Duration>,
+    /// Black's increment per move.
+    pub binc: Option<Duration>,
+    /// Moves left until the next time control.
+    pub movestogo: Option<u32>,
+    /// Search this many plies only.
+    pub depth: Option<u32>,
+    /// Search this many nodes only.
+    pub nodes: Option<u64>,
+    /// Search for a mate in this many moves.
+    pub mate: Option<u32>,
+    /// Search exactly this long.
+    pub movetime: Option<Duration>,
+    /// Search until told to stop.
+    pub infinite: bool,
+    /// Think on the opponent's time, in the position after the move the engine expects them to play.
+    /// The time limits only apply from `ponderhit` on.
+    pub ponder: bool,
+    /// Don't search, but count the positions this many plies deep, split up by the first move.
+    pub perft: Option<u32>,
+    /// Only search these moves, as sent by the GUI. Empty means every move.
+    /// See [GoParams::search_moves] for the moves that can actually be searched.
+    pub searchmoves: Vec<String>,
+}
+
+impl GoParams {
+    /// Parse the words following `go`.
+    /// Returns an error describing the first unknown keyword or malformed value.
+    pub fn parse<'a>(words: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut params = Self::default();
+        let mut words = words.into_iter().peekable();
+        while let Some(word) = words.next() {
+            if word == "searchmoves" {
+                while let Some(m) = words.next_if(|w| !GO_KEYWORDS.contains(w)) {
+                    params.searchmoves.push(m.to_string());
+                }
+                continue;
+            }
+            let mut value = || words.next().ok_or(format!("Missing value for '{word}'"));
+            match word {
+                "wtime" => params.wtime = Some(parse_millis(value()?)?),
+                "btime" => params.btime = Some(parse_millis(value()?)?),
+                "winc" => params.winc = Some(parse_millis(value()?)?),
+                "binc" => params.binc = Some(parse_millis(value()?)?),
+                "movestogo" => params.movestogo = Some(parse_number(value()?)?),
+                "depth" => params.depth = Some(parse_number(value()?)?),
+                "nodes" => params.nodes = Some(parse_number(value()?)?),
+                "mate" => params.mate = Some(parse_mate(value()?)?),
+                "movetime" => params.movetime = Some(parse_millis(value()?)?),
+                "infinite" => params.infinite = true,
+                "ponder" => params.ponder = true,
+                "perft" => params.perft = Some(parse_number(value()?)?),
+                other => return Err(format!("Unknown parameter to 'go': '{other}'")),
+            }
+        }
+        Ok(params)
+    }
+
+    /// Turn the parameters into limits for a search by the given side,
+    /// keeping `move_overhead` in reserve. Time on the clock is only used if no fixed move time is given.
+    pub fn limits(&self, side: Color, move_overhead: Duration) -> Limits {
+        let (time_left, increment) = match side {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        let time = if self.infinite {
+            None
+        } else if let Some(movetime) = self.movetime {
+            Some(TimeLimits::fixed(movetime, move_overhead))
+        } else {
+            time_left.map(|time_left| {
+                TimeLimits::from_clock(
+                    time_left,
+                    increment.unwrap_or_default(),
+                    self.movestogo,
+                    move_overhead,
+                )
+            })
+        };
+
+        Limits {
+            depth: self.depth,
+            nodes: self.nodes,
+            mate: self.mate,
+            time,
+            search_moves: Vec::new(),
+        }
+    }
+
+    /// The moves listed after `searchmoves` that are legal on the board, each once.
+    /// Returns a warning for every move left out, because it can't be read or isn't legal.
+    pub fn search_moves(&self, board: &Board) -> (Vec<Move>, Vec<String>) {
+        let legal = board.generate_moves();
+        let mut moves = Vec::new();
+        let mut warnings = Vec::new();
+        for word in &self.searchmoves {
+            match parse_move(word) {
+                Some(m) if legal.contains(&m) => {
+                    if !moves.contains(&m) {
+                        moves.push(m);
+                    }
+                }
+                Some(_) => warnings.push(format!("Ignoring illegal move '{word}' in searchmoves")),
+                None => warnings.push(format!("Ignoring unknown move '{word}' in searchmoves")),
+            }
+        }
+        (moves, warnings)
+    }
+}
+
+/// Read a move in long algebraic notation, like "e2e4" or "e7e8q".
+/// Returns None instead of panicking like [Move::from_str] when it isn't one.
+pub fn parse_move(word: &str) -> Option<Move> {
+    try_square_from_algebraic(word.get(0..2)?)?;
+    try_square_from_algebraic(word.get(2..4)?)?;
+    matches!(word.get(4..)?, "" | "n" | "b" | "r" | "q").then(|| Move::from_str(word))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Expected a number, found '{value}'"))
+}
+
+/// Read the number of moves to find a mate in.
+/// There is no mate in 0 moves, and no mate deeper than the search can go is ever found.
+fn parse_mate(value: &str) -> Result<u32, String> {
+    match parse_number(value)? {
+        0 => Err("Expected a mate in at least 1 move".to_string()),
+        moves => Ok(moves.min(MAX_DEPTH / 2)),
+    }
+}
+
+/// Read a number of milliseconds. GUIs may send negative times once the clock has run out.
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    let millis: i64 = parse_number(value)?;
+    Ok(Duration::from_millis(millis.max(0) as u64))
+}
+
+/// Format a score the way it is reported in an `info` line: `cp <centipawns>`,
+/// or `mate <moves>` for a forced mate, negative when it's the engine being mated.
+pub fn format_score(score: i32) -> String {
+    if score >= MATE_BOUND {
+        format!("mate {}", (MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("mate {}", -(MATE + score) / 2)
+    } else {
+        format!("cp {score}")
+    }
+}
+
+/// The kind of value a UCI option takes, with its default and allowed values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// On or off.
+    Check { default: bool },
+    /// A whole number in a range.
+    Spin { default: i64, min: i64, max: i64 },
+    /// One of a few predefined strings.
+    Combo {
+        default: &'static str,
+        vars: &'static [&'static str],
+    },
+    /// Any string.
+    String { default: &'static str },
+    /// No value, setting the option triggers an action.
+    Button,
+}
+
+/// An option the GUI can change with `setoption`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionType,
+}
+
+impl std::fmt::Display for UciOption {
+    /// Formats the option the way it is advertised in response to `uci`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match self.kind {
+            OptionType::Check { default } => write!(f, "check default {default}"),
+            OptionType::Spin { default, min, max } => {
+                write!(f, "spin default {default} min {min} max {max}")
+            }
+            OptionType::Combo { default, vars } => {
+                write!(f, "combo default {default}")?;
+                vars.iter().try_for_each(|var| write!(f, " var {var}"))
+            }
+            OptionType::String { default: "" } => write!(f, "string default <empty>"),
+            OptionType::String { default } => write!(f, "string default {default}"),
+            OptionType::Button => write!(f, "button"),
+        }
+    }
+}
+
+/// Every option the engine understands.
+pub const OPTIONS: &[UciOption] = &[
+    UciOption {
+        name: "Hash",
+        kind: OptionType::Spin {
+            default: DEFAULT_HASH_MB as i64,
+            min: 1,
+            max: MAX_HASH_MB as i64,
+        },
+    },
+    UciOption {
+        name: "Clear Hash",
+        kind: OptionType::Button,
+    },
+    UciOption {
+        name: "Threads",
+        kind: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    },
+    UciOption {
+        name: "MultiPV",
+        kind: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    },
+    UciOption {
+        name: "Move Overhead",
+        kind: OptionType::Spin {
+            default: DEFAULT_MOVE_OVERHEAD.as_millis() as i64,
+            min: 0,
+            max: 5000,
+        },
+    },
+    UciOption {
+        name: "Ponder",
+        kind: OptionType::Check { default: false },
+    },
+    UciOption {
+        name: "PVS",
+        kind: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: "Null Move Pruning",
+        kind: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: "Late Move Reductions",
+        kind: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: "Check Extensions",
+        kind: OptionType::Check { default: true },
+    },
+];
+
+/// The engine's configuration, as set by the GUI through [OPTIONS].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Size of the transposition table in MB.
+    pub hash: usize,
+    /// Time kept in reserve for communication with the GUI.
+    pub move_overhead: Duration,
+    /// Whether the GUI will let the engine think on the opponent's time.
+    pub ponder: bool,
+    /// How to search, which covers the threads to use, the number of lines to report
+    /// and the search techniques turned on.
+    pub search: SearchOptions,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            hash: DEFAULT_HASH_MB,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            ponder: false,
+            search: SearchOptions::default(),
+        }
+    }
+}
+
+impl Options {
+    /// Parse the words following `setoption`, which are `name <name> [value <value>]`.
+    /// Both the name and the value may contain spaces.
+    pub fn parse_setoption<'a>(
+        words: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(String, Option<String>), String> {
+        let mut words = words.into_iter();
+        if words.next() != Some("name") {
+            return Err("Expected 'setoption name <name> [value <value>]'".to_string());
+        }
+        let name = words
+            .by_ref()
+            .take_while(|&w| w != "value")
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = words.collect::<Vec<_>>().join(" ");
+        Ok((name, Some(value).filter(|v| !v.is_empty())))
+    }
+
+    /// Set an option by name, ignoring case like the UCI protocol asks.
+    /// Returns the option's proper name, so buttons can be acted upon.
+    /// Unknown options and values that don't fit the option are rejected, leaving the options unchanged.
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<&'static str, String> {
+        let option = OPTIONS
+            .iter()
+            .find(|o| o.name.eq_ignore_ascii_case(name))
+            .ok_or(format!("Unknown option '{name}'"))?;
+        match (option.name, option.parse_value(value)?) {
+            ("Hash", OptionValue::Spin(n)) => self.hash = n as usize,
+            ("Threads", OptionValue::Spin(n)) => self.search.threads = n as usize,
+            ("MultiPV", OptionValue::Spin(n)) => self.search.multi_pv = n as usize,
+            ("Move Overhead", OptionValue::Spin(n)) => {
+                self.move_overhead = Duration::from_millis(n as u64)
+            }
+            ("Ponder", OptionValue::Check(on)) => self.ponder = on,
+            ("PVS", OptionValue::Check(on)) => self.search.pvs = on,
+            ("Null Move Pruning", OptionValue::Check(on)) => self.search.null_move = on,
+            ("Late Move Reductions", OptionValue::Check(on)) => self.search.lmr = on,
+            ("Check Extensions", OptionValue::Check(on)) => self.search.check_extensions = on,
+            ("Clear Hash", OptionValue::Button) => {}
+            (name, value) => unreachable!("option '{name}' can't be set to {value:?}"),
+        }
+        Ok(option.name)
+    }
+}
+
+/// A value given to `setoption`, checked against the type of the option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    String(String),
+    Button,
+}
+
+impl UciOption {
+    /// Check that a value given to `setoption` fits this option.
+    pub fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        if self.kind == OptionType::Button {
+            return Ok(OptionValue::Button);
+        }
+        let value = value.ok_or(format!("Missing value for option '{}'", self.name))?;
+        match self.kind {
+            OptionType::Check { .. } => match value {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(format!("Expected true or false, found '{value}'")),
+            },
+            OptionType::Spin { min, max, .. } => {
+                let n = parse_number(value)?;
+                if (min..=max).contains(&n) {
+                    Ok(OptionValue::Spin(n))
+                } else {
+                    Err(format!(
+                        "Value {n} for option '{}' is outside {min}..={max}",
+                        self.name
+                    ))
+                }
+            }
+            OptionType::Combo { vars, .. } => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| OptionValue::String(var.to_string()))
+                .ok_or(format!(
+                    "'{value}' is not a choice for option '{}'",
+                    self.name
+                )),
+            OptionType::String { .. } => Ok(OptionValue::String(value.to_string())),
+            OptionType::Button => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// Random keys for Zobrist hashing of a [crate::board::Board].
+// A position's hash is the XOR of the keys for every feature of the position,
+// so making a move only has to XOR the keys of the features that changed.
+
+/// Generate `N` pseudo random keys at compile time using SplitMix64.
+const fn keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut state = seed;
+    let mut i = 0;
+    while i < N {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Keys for a piece standing on a square, indexed by `piece as usize * 64 + square`.
+pub(crate) const PIECE_SQUARE: [u64; 14 * 64] = keys(1);
+
+/// Key included when black is to move.
+pub(crate) const BLACK_TO_MOVE: u64 = keys::<1>(2)[0];
+
+/// Keys for each castling right, ordered white king side, white queen side, black king side, black queen side.
+pub(crate) const CASTLING: [u64; 4] = keys(3);
+
+/// Keys for the file of an en pessant square, included only when the capture is possible.
+pub(crate) const EN_PESSANT_FILE: [u64; 8] = keys(4);
@@ -0,0 +1,28 @@
+use justok::bitboard::{
+    bishop_attacks, rook_attacks, sliding_attacks, BISHOP_DIRECTIONS, ROOK_DIRECTIONS,
+};
+
+#[test]
+fn magic_lookups_match_ray_walking() {
+    // Deterministic pseudo random occupancies with varying density.
+    let mut state: u64 = 0x1234_5678_9ABC_DEF0;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..200 {
+        let occupied = random() & random();
+        for square in 0..64 {
+            assert_eq!(
+                rook_attacks(square, occupied),
+                sliding_attacks(square, occupied, &ROOK_DIRECTIONS)
+            );
+            assert_eq!(
+                bishop_attacks(square, occupied),
+                sliding_attacks(square, occupied, &BISHOP_DIRECTIONS)
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use justok::board::Board;
+use justok::eval::evaluate;
+
+/// Flip a FEN string so white and black swap places. Castling and en pessant must be '-'.
+fn mirror(fen: &str) -> String {
+    let fields: Vec<_> = fen.split_whitespace().collect();
+    let placement = fields[0]
+        .split('/')
+        .rev()
+        .map(|rank| {
+            rank.chars()
+                .map(|c| match c.is_uppercase() {
+                    true => c.to_ascii_lowercase(),
+                    false => c.to_ascii_uppercase(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    let side = if fields[1] == "w" { "b" } else { "w" };
+    format!("{placement} {side} - - 0 1")
+}
+
+const POSITIONS: [&str; 5] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w - - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w - - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b - - 3 3",
+];
+
+#[test]
+fn start_position_is_balanced() {
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(evaluate(&board), 0);
+}
+
+#[test]
+fn symmetric() {
+    for fen in POSITIONS {
+        assert_eq!(
+            evaluate(&Board::from_fen(fen)),
+            evaluate(&Board::from_fen(&mirror(fen))),
+            "{fen}"
+        );
+    }
+}
+
+#[test]
+fn side_to_move_perspective() {
+    for fen in POSITIONS {
+        let flipped = match fen.contains(" w ") {
+            true => fen.replace(" w ", " b "),
+            false => fen.replace(" b ", " w "),
+        };
+        assert_eq!(
+            evaluate(&Board::from_fen(fen)),
+            -evaluate(&Board::from_fen(&flipped)),
+            "{fen}"
+        );
+    }
+}
+
+#[test]
+fn material_matters() {
+    let up_a_queen = Board::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert!(evaluate(&up_a_queen) > 800);
+    let down_a_knight = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+    assert!(evaluate(&down_a_knight) < -250);
+}
+
+#[test]
+fn passed_pawns_matter_in_endgame() {
+    // A pawn about to promote is worth more than one at home, once the board has emptied.
+    let advanced = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    let at_home = Board::from_fen("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+    assert!(evaluate(&advanced) > evaluate(&at_home));
+}
@@ -0,0 +1,80 @@
+use justok::board::{Board, FenError};
+
+#[test]
+fn round_trip() {
+    let positions = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "8/8/8/8/8/8/8/k6K b - - 50 120",
+    ];
+    for fen in positions {
+        assert_eq!(Board::try_from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn four_fields() {
+    let board =
+        Board::try_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").unwrap();
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("", FenError::FieldCount(0)),
+        ("8/8/8/8/8/8/8/8 w - - 0 1 extra", FenError::FieldCount(7)),
+        ("8/8/8/8/8/8/8 w - - 0 1", FenError::RankCount(7)),
+        (
+            "8/8/8/8/8/8/8/7 w - - 0 1",
+            FenError::RankLength {
+                rank: 1,
+                squares: 7,
+            },
+        ),
+        (
+            "8/8/ppppppppp/8/8/8/8/8 w - - 0 1",
+            FenError::RankLength {
+                rank: 6,
+                squares: 9,
+            },
+        ),
+        ("8/8/8/8/8/8/8/7x w - - 0 1", FenError::UnknownPiece('x')),
+        (
+            "8/8/8/8/8/8/8/8 x - - 0 1",
+            FenError::SideToMove("x".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w KX - 0 1",
+            FenError::Castling("KX".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w kK - 0 1",
+            FenError::Castling("kK".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w - e4 0 1",
+            FenError::EnPessant("e4".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w - i3 0 1",
+            FenError::EnPessant("i3".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w - - a 1",
+            FenError::HalfMoveClock("a".to_owned()),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w - - 0 -1",
+            FenError::FullMoveCounter("-1".to_owned()),
+        ),
+    ];
+    for (fen, expected) in cases {
+        assert_eq!(Board::try_from_fen(fen), Err(expected), "{fen}");
+    }
+}
@@ -0,0 +1,95 @@
+use justok::board::{Board, Outcome};
+use justok::game::Game;
+use justok::{Color, Move};
+
+fn play(fen: &str, moves: &[&str]) -> Game {
+    let mut game = Game::new(Board::from_fen(fen));
+    for m in moves {
+        game.apply(Move::from_str(m));
+    }
+    game
+}
+
+#[test]
+fn threefold_repetition() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    let twice = play(start, &shuffle);
+    assert!(!twice.is_threefold_repetition());
+
+    let thrice = play(start, &[shuffle, shuffle].concat());
+    assert!(thrice.is_threefold_repetition());
+    assert_eq!(thrice.moves().len(), 8);
+    assert_eq!(thrice.history().len(), 8);
+}
+
+#[test]
+fn repetition_broken_by_pawn_move() {
+    // The pawn move in between makes the earlier positions unreachable.
+    let start = "4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1";
+    let game = play(
+        start,
+        &[
+            "e1d1", "e8d8", "d1e1", "d8e8", "e2e3", "e8d8", "e1d1", "d8e8", "d1e1",
+        ],
+    );
+    assert!(!game.is_threefold_repetition());
+}
+
+#[test]
+fn fifty_move_draw() {
+    let game = play("4k3/8/8/8/8/8/8/R3K3 w - - 98 80", &["a1a2"]);
+    assert!(!game.is_fifty_move_draw());
+    let game = play("4k3/8/8/8/8/8/8/R3K3 w - - 98 80", &["a1a2", "e8d8"]);
+    assert!(game.is_fifty_move_draw());
+    let game = play("4k3/3p4/8/8/8/8/8/R3K3 w - - 98 80", &["a1a2", "d7d6"]);
+    assert!(!game.is_fifty_move_draw());
+}
+
+#[test]
+fn insufficient_material() {
+    let insufficient = [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/B1B1K1B1 w - - 0 1",
+    ];
+    for fen in insufficient {
+        assert!(play(fen, &[]).is_insufficient_material(), "{fen}");
+    }
+
+    let sufficient = [
+        "4k3/8/8/8/8/8/8/4K2R w - - 0 1",
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+        "4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+    ];
+    for fen in sufficient {
+        assert!(!play(fen, &[]).is_insufficient_material(), "{fen}");
+    }
+}
+
+#[test]
+fn outcome() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(play(start, &[]).outcome(), None);
+
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    assert_eq!(
+        play(start, &[shuffle, shuffle].concat()).outcome(),
+        Some(Outcome::ThreefoldRepetition)
+    );
+
+    let game = play("4k3/8/8/8/8/8/8/R3K3 w - - 98 80", &["a1a2", "e8d8"]);
+    assert_eq!(game.outcome(), Some(Outcome::FiftyMoveRule));
+
+    let game = play("4k3/8/8/8/8/8/8/3qK3 w - - 0 1", &["e1d1"]);
+    assert_eq!(game.outcome(), Some(Outcome::InsufficientMaterial));
+
+    // Mate on the move that reaches the fifty move limit still counts as mate.
+    let game = play("k7/8/1K6/8/8/8/8/7R w - - 99 80", &["h1h8"]);
+    assert_eq!(game.outcome(), Some(Outcome::Checkmate(Color::White)));
+}
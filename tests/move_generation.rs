@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 
-use justok::board::Board;
-use justok::Move;
+use justok::board::{Board, GenType, Outcome};
+use justok::{Color, Move, MoveList, Piece};
 
 #[test]
 fn opening_moves() {
@@ -825,121 +825,185 @@ fn en_pessant_4() {
 #[test]
 fn checkmate_1() {
     let position = "1R3k2/2R5/8/8/8/1K6/8/8 b - - 0 1 ";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::White)));
 }
 
 #[test]
 fn checkmate_2() {
     let position = "8/8/1k6/8/8/8/2r5/1r3K2 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::Black)));
 }
 
 #[test]
 fn checkmate_3() {
     let position = "8/6N1/3R4/6k1/5Pp1/1K2P3/8/4B1R1 b - f3 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::White)));
 }
 
 #[test]
 fn checkmate_4() {
     let position = "4b1r1/8/1k2p3/5pP1/6K1/3r4/6n1/8 w - f6 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::Black)));
 }
 
 #[test]
 fn checkmate_5() {
     let position = "kr6/ppN5/8/8/8/8/2K5/8 b - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::White)));
 }
 
 #[test]
 fn checkmate_6() {
     let position = "8/2k5/8/8/8/8/PPn5/KR6 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::Black)));
 }
 
 #[test]
 fn checkmate_7() {
     let position = "k1K5/p1N5/8/8/8/8/8/8 b - - 0 1";
-    let moves = Board::from_fen(position).generate_moves();
+    let board = Board::from_fen(position);
+    let moves = board.generate_moves();
     for m in moves.clone() {
         println!("{m}");
     }
-    assert!(moves.len() == 0);
+    assert!(moves.is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::White)));
 }
 
 #[test]
 fn checkmate_8() {
     let position = "8/8/8/8/8/8/P1n5/K1k5 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Checkmate(Color::Black)));
 }
 
 #[test]
 fn stalemate_1() {
-    let position = "8/8/8/8/8/8/P1n5/K1k5 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    // Used to be "8/8/8/8/8/8/P1n5/K1k5 w - - 0 1", which is the mate from checkmate_8.
+    let position = "8/8/8/8/8/n7/P1k5/K7 w - - 0 1";
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 
 #[test]
 fn stalemate_2() {
     let position = "k7/1R6/2K5/8/8/8/8/8 b - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_3() {
     let position = "8/8/8/8/8/2k5/1r6/K7 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_4() {
     let position = "k7/8/2N5/8/8/2K5/1R6/8 b - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_5() {
     let position = "8/1r6/2k5/8/8/2n5/8/K7 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_6() {
     let position = "k7/2Q5/8/8/8/2K5/8/8 b - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_7() {
     let position = "8/8/5R2/4k1P1/3R4/2K5/8/8 b - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_8() {
     let position = "8/8/2k5/8/8/8/2q5/K7 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_9() {
     let position = "8/8/2k5/3r4/4K1p1/5r2/8/8 w - - 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_10() {
     let position = "5bnr/4p1pq/4Qpkr/7p/7P/4P3/PPPP1PP1/RNB1KBNR b KQ - 2 10";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_11() {
     let position = "rnb1kbnr/pppp1pp1/4p3/7p/7P/4qPKR/4P1PQ/5BNR w kq - 2 10";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 #[test]
 fn stalemate_12() {
     let position = "8/8/R7/4k3/4Pp2/2P2P2/7B/1K6 b - e3 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 
 #[test]
 fn stalemate_13() {
     let position = "1k6/7b/2p2p2/4pP2/4K3/r7/8/8 w - e6 0 1";
-    assert!(Board::from_fen(position).generate_moves().len() == 0);
+    let board = Board::from_fen(position);
+    assert!(board.generate_moves().is_empty());
+    assert!(!board.is_in_check());
+    assert_eq!(board.outcome(), Some(Outcome::Stalemate));
 }
 
 #[test]
@@ -1648,3 +1712,90 @@ fn promotion_3() {
         // TODO: Test undo's too
     }
 }
+
+#[test]
+fn captures_only() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        // In check, only captures that resolve it are legal.
+        "4k3/8/8/8/8/5n2/3PPP2/r3K2R w K - 0 1",
+    ];
+    for fen in fens {
+        let board = Board::from_fen(fen);
+        let captures = generate_set(&board, GenType::Captures);
+        let expected: HashSet<Move> = board
+            .generate_moves()
+            .into_iter()
+            .filter(|m| {
+                let is_pawn = matches!(board.at(m.from), Some(Piece::PawnWhite | Piece::PawnBlack));
+                let en_pessant = is_pawn && m.from % 8 != m.to % 8;
+                let queen = matches!(m.promote_to, Some(Piece::QueenWhite | Piece::QueenBlack));
+                board.at(m.to).is_some() || en_pessant || queen
+            })
+            .collect();
+        assert_eq!(captures, expected, "{fen}");
+    }
+}
+
+fn generate_set(board: &Board, gen: GenType) -> HashSet<Move> {
+    let mut moves = MoveList::new();
+    board.generate(gen, &mut moves);
+    let set: HashSet<Move> = moves.iter().copied().collect();
+    assert_eq!(set.len(), moves.len(), "no move is generated twice");
+    set
+}
+
+const GEN_TYPE_POSITIONS: [&str; 8] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    // Checks by a pawn that can be captured en pessant, by a knight, by a slider and double check.
+    "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+    "4k3/8/8/8/8/5n2/3PPP2/r3K2R w K - 0 1",
+    "4k3/8/8/8/1b6/8/2nPP3/R3K2R w KQ - 0 1",
+];
+
+#[test]
+fn captures_and_quiets_split_all_moves() {
+    for fen in GEN_TYPE_POSITIONS {
+        let board = Board::from_fen(fen);
+        let all = generate_set(&board, GenType::All);
+        let captures = generate_set(&board, GenType::Captures);
+        let quiets = generate_set(&board, GenType::Quiets);
+        assert!(captures.is_disjoint(&quiets), "{fen}");
+        assert_eq!(&captures | &quiets, all, "{fen}");
+    }
+}
+
+#[test]
+fn evasions() {
+    for fen in GEN_TYPE_POSITIONS {
+        let board = Board::from_fen(fen);
+        let all = generate_set(&board, GenType::All);
+        assert_eq!(generate_set(&board, GenType::Evasions), all, "{fen}");
+
+        // Fewer pseudo legal moves are generated in check.
+        if board.is_in_check() {
+            let mut all = MoveList::new();
+            let mut evasions = MoveList::new();
+            board.generate_pseudo(GenType::All, &mut all);
+            board.generate_pseudo(GenType::Evasions, &mut evasions);
+            assert!(evasions.len() < all.len(), "{fen}");
+        }
+    }
+}
+
+#[test]
+fn generate_appends_to_list() {
+    let board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+    let mut moves = MoveList::new();
+    board.generate(GenType::Captures, &mut moves);
+    let captures = moves.len();
+    board.generate(GenType::Quiets, &mut moves);
+    assert_eq!(moves.len(), board.generate_moves().len());
+    assert_eq!(moves[..captures], [Move::from_str("b4f4")]);
+}
@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+
+use justok::board::Board;
+use justok::movepick::{mvv_lva, History, MovePicker};
+use justok::{Move, Piece};
+
+const POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    // In check.
+    "4k3/8/8/8/1b6/8/3PP3/R3K2R w KQ - 0 1",
+];
+
+fn pick_all(board: &Board, tt_move: Option<Move>, killers: [Option<Move>; 2]) -> Vec<Move> {
+    let history = History::new();
+    let mut picker = MovePicker::new(board, tt_move, killers);
+    let mut moves = Vec::new();
+    while let Some(m) = picker.next(board, &history) {
+        moves.push(m);
+    }
+    moves
+}
+
+#[test]
+fn picks_every_legal_move_once() {
+    for fen in POSITIONS {
+        let board = Board::from_fen(fen);
+        let legal: HashSet<Move> = board.generate_moves().into_iter().collect();
+        // Illegal guesses, like those from a hash collision, must be ignored.
+        let guesses = [
+            None,
+            Some(Move::from_str("e2e4")),
+            Some(Move::from_str("a1a8")),
+            Some(Move::from_str("e8e7")),
+            board.generate_moves().last().copied(),
+        ];
+        for tt_move in guesses {
+            for killer in guesses {
+                let picked = pick_all(&board, tt_move, [killer, Some(Move::from_str("h2h3"))]);
+                let unique: HashSet<Move> = picked.iter().copied().collect();
+                assert_eq!(picked.len(), unique.len(), "{fen}: {picked:?}");
+                assert_eq!(unique, legal, "{fen}");
+            }
+        }
+    }
+}
+
+#[test]
+fn order_of_moves() {
+    let board = Board::from_fen(POSITIONS[1]);
+    let tt_move = Move::from_str("a2a3");
+    let killer = Move::from_str("g2g3");
+    let picked = pick_all(&board, Some(tt_move), [Some(killer), None]);
+    assert_eq!(picked[0], tt_move);
+
+    // Then captures that don't lose material, best victim first, then the killer.
+    let captures: Vec<Move> = picked[1..]
+        .iter()
+        .copied()
+        .take_while(|&m| board.is_capture(m))
+        .collect();
+    assert!(captures.iter().all(|&m| board.see(m) >= 0));
+    assert!(captures
+        .windows(2)
+        .all(|w| mvv_lva(&board, w[0]) >= mvv_lva(&board, w[1])));
+    assert_eq!(picked[captures.len() + 1], killer);
+
+    // Captures that lose material come last.
+    let bad_captures: Vec<Move> = picked
+        .iter()
+        .rev()
+        .copied()
+        .take_while(|&m| board.is_capture(m))
+        .collect();
+    assert!(!bad_captures.is_empty());
+    assert!(bad_captures.iter().all(|&m| board.see(m) < 0));
+    assert_eq!(captures.len() + bad_captures.len(), 8);
+}
+
+#[test]
+fn history_orders_quiets() {
+    let board = Board::from_fen(POSITIONS[0]);
+    let mut history = History::new();
+    let favourite = Move::from_str("b1c3");
+    history.update(&board, favourite, 100);
+    history.update(&board, Move::from_str("e2e4"), -100);
+    let mut picker = MovePicker::new(&board, None, [None; 2]);
+    assert_eq!(picker.next(&board, &history), Some(favourite));
+    let mut last = None;
+    while let Some(m) = picker.next(&board, &history) {
+        last = Some(m);
+    }
+    assert_eq!(last, Some(Move::from_str("e2e4")));
+}
+
+#[test]
+fn history_is_bounded() {
+    let board = Board::from_fen(POSITIONS[0]);
+    let mut history = History::new();
+    let m = Move::from_str("e2e4");
+    for _ in 0..1000 {
+        history.update(&board, m, 4000);
+    }
+    assert!(history.get(&board, m) <= 16_384);
+    // Black's moves have their own scores.
+    let black = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    assert_eq!(history.get(&black, m), 0);
+}
+
+#[test]
+fn mvv_lva_order() {
+    let board = Board::from_fen("4k3/8/8/3q1r2/2P1P1N1/8/8/3QK3 w - - 0 1");
+    let score = |m: &str| mvv_lva(&board, Move::from_str(m));
+    assert_eq!(score("c4d5"), score("e4d5"));
+    // Winning the queen with a pawn beats winning it with the queen, which beats winning the rook.
+    assert!(score("e4d5") > score("d1d5"));
+    assert!(score("d1d5") > score("e4f5"));
+    assert!(score("e4f5") > score("e1d2"));
+}
+
+#[test]
+fn captured_piece() {
+    let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1");
+    assert_eq!(
+        board.captured_piece(Move::from_str("d1d5")),
+        Some(Piece::QueenBlack)
+    );
+    assert_eq!(board.captured_piece(Move::from_str("d1d4")), None);
+    // The pawn taken en pessant isn't on the square moved to.
+    let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(
+        board.captured_piece(Move::from_str("e5d6")),
+        Some(Piece::PawnBlack)
+    );
+}
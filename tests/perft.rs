@@ -1,26 +1,12 @@
 use justok::board::Board;
-
-fn perft(board: Board, depth: usize) -> u128 {
-    let moves = board.generate_moves();
-    if depth == 1 {
-        return moves.len() as u128;
-    };
-
-    moves
-        .iter()
-        .map(|&moove| {
-            let mut board_with_move = board.clone();
-            board_with_move.apply(moove);
-            perft(board_with_move, depth - 1)
-        })
-        .sum()
-}
+use justok::perft::{divide, perft};
+use justok::Move;
 
 #[test]
 fn perft_standard() {
     let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    let expected: [u128; 6] = [20, 400, 8902, 197281, 4865609, 119060324];
-    let actual = perft(board, 5);
+    let expected: [u64; 6] = [20, 400, 8902, 197281, 4865609, 119060324];
+    let actual = perft(&board, 5);
     assert_eq!(expected[4], actual);
 }
 
@@ -28,15 +14,94 @@ fn perft_standard() {
 fn perft_kiwi_pete() {
     let board =
         Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
-    let expected: [u128; 6] = [48, 2039, 97862, 4085603, 193690690, 8031647685];
-    let actual = perft(board, 4);
+    let expected: [u64; 6] = [48, 2039, 97862, 4085603, 193690690, 8031647685];
+    let actual = perft(&board, 4);
     assert_eq!(expected[3], actual);
 }
 
 #[test]
 fn perft_tricky() {
     let board = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
-    let expected: [u128; 5] = [44, 1486, 62379, 2103487, 89941194];
-    let actual = perft(board, 4);
+    let expected: [u64; 5] = [44, 1486, 62379, 2103487, 89941194];
+    let actual = perft(&board, 4);
+    assert_eq!(expected[3], actual);
+}
+
+#[test]
+fn perft_position_4() {
+    let board =
+        Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+    let expected: [u64; 5] = [6, 264, 9467, 422333, 15833292];
+    let actual = perft(&board, 4);
     assert_eq!(expected[3], actual);
 }
+
+#[test]
+fn perft_endgame() {
+    let board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+    let expected: [u64; 6] = [14, 191, 2812, 43238, 674624, 11030083];
+    let actual = perft(&board, 5);
+    assert_eq!(expected[4], actual);
+}
+
+/// Too slow for debug builds, run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn perft_standard_deep() {
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let expected: [u64; 6] = [20, 400, 8902, 197281, 4865609, 119060324];
+    let actual = perft(&board, 6);
+    assert_eq!(expected[5], actual);
+}
+
+#[test]
+fn perft_shallow() {
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(perft(&board, 0), 1);
+    assert_eq!(perft(&board, 1), 20);
+}
+
+#[test]
+fn divide_by_first_move() {
+    let board =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let counts = divide(&board, 3);
+    assert_eq!(counts.len(), 48);
+    assert_eq!(counts.iter().map(|(_, n)| n).sum::<u64>(), 97862);
+    // Reference counts from Stockfish.
+    assert!(counts.contains(&(Move::from_str("e1g1"), 2059)));
+    assert!(counts.contains(&(Move::from_str("d5e6"), 2241)));
+    // No move has been made at depth 0.
+    assert!(divide(&board, 0).is_empty());
+}
+
+/// Making and then unmaking every move in the tree must leave the board exactly as it was.
+fn assert_unmake_restores(board: &mut Board, depth: usize) {
+    if depth == 0 {
+        return;
+    }
+    for moove in board.generate_moves() {
+        let before = *board;
+        let mut applied = *board;
+        applied.apply(moove);
+
+        let undo = board.make_move(moove);
+        assert_eq!(*board, applied, "make {moove} on {}", before.to_fen());
+        assert_unmake_restores(board, depth - 1);
+        board.unmake_move(moove, undo);
+        assert_eq!(*board, before, "unmake {moove} on {}", before.to_fen());
+    }
+}
+
+#[test]
+fn unmake_restores_board() {
+    let positions = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    for fen in positions {
+        assert_unmake_restores(&mut Board::from_fen(fen), 3);
+    }
+}
@@ -0,0 +1,311 @@
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use justok::board::Board;
+use justok::game::Game;
+use justok::search::{search, search_until, Limits, SearchOptions, SearchResult, Signals, MATE};
+use justok::time::TimeLimits;
+use justok::tt::TranspositionTable;
+use justok::Move;
+
+fn search_fen(fen: &str, depth: u32) -> SearchResult {
+    search(&Game::new(Board::from_fen(fen)), &Limits::to_depth(depth))
+}
+
+#[test]
+fn mate_in_one() {
+    let result = search_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1", 3);
+    assert_eq!(result.best_move, Some(Move::from_str("h1h8")));
+    assert_eq!(result.score, MATE - 1);
+}
+
+#[test]
+fn mate_in_two() {
+    // Ra7 can't be answered, then Rb8 mates.
+    let result = search_fen("k7/8/2K5/8/8/8/8/1R5R w - - 0 1", 4);
+    assert_eq!(result.score, MATE - 3);
+}
+
+#[test]
+fn captures_hanging_queen() {
+    let result = search_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 2);
+    assert_eq!(result.best_move, Some(Move::from_str("d1d5")));
+}
+
+#[test]
+fn avoids_losing_queen() {
+    // Taking the pawn on b7 loses the queen to the rook.
+    let result = search_fen("1r2k3/1p6/8/8/8/8/8/1Q2K3 w - - 0 1", 3);
+    assert_ne!(result.best_move, Some(Move::from_str("b1b7")));
+}
+
+#[test]
+fn principal_variation_is_legal() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let result = search_fen(fen, 3);
+    assert_eq!(result.depth, 3);
+    assert_eq!(result.pv.first().copied(), result.best_move);
+    let mut board = Board::from_fen(fen);
+    for m in result.pv {
+        assert!(
+            board.generate_moves().contains(&m),
+            "{m} in {}",
+            board.to_fen()
+        );
+        board.apply(m);
+    }
+}
+
+#[test]
+fn no_legal_moves() {
+    let stalemate = search_fen("k7/2Q5/8/8/8/2K5/8/8 b - - 0 1", 3);
+    assert_eq!(stalemate.best_move, None);
+    assert_eq!(stalemate.score, 0);
+
+    let checkmate = search_fen("k1K5/p1N5/8/8/8/8/8/8 b - - 0 1", 3);
+    assert_eq!(checkmate.best_move, None);
+    assert_eq!(checkmate.score, -MATE);
+}
+
+#[test]
+fn avoids_repetition_when_winning() {
+    // White is a queen up. Going back with the queen would repeat the position for the third time.
+    let mut game = Game::new(Board::from_fen("7k/8/8/8/8/8/8/Q3K3 w - - 0 1"));
+    for m in [
+        "a1a2", "h8g8", "a2a1", "g8h8", "a1a2", "h8g8", "a2a1", "g8h8",
+    ] {
+        game.apply(Move::from_str(m));
+    }
+    let result = search(&game, &Limits::to_depth(3));
+    assert!(result.score > 0);
+}
+
+#[test]
+fn node_limit() {
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    let limits = Limits {
+        nodes: Some(5_000),
+        ..Limits::default()
+    };
+    let result = search(&game, &limits);
+    assert!(result.best_move.is_some());
+    assert!(result.nodes <= 5_000);
+}
+
+#[test]
+fn node_limit_within_first_iteration() {
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    let limits = Limits {
+        nodes: Some(100),
+        ..Limits::default()
+    };
+    let result = search(&game, &limits);
+    assert!(game
+        .board()
+        .generate_moves()
+        .contains(&result.best_move.unwrap()));
+    assert!(result.nodes <= 100);
+}
+
+#[test]
+fn stopped_before_searching() {
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    let signals = Signals::default();
+    signals.stop.store(true, Ordering::Relaxed);
+    let result = search_until(
+        &game,
+        &Limits::default(),
+        &SearchOptions::default(),
+        &TranspositionTable::default(),
+        &signals,
+    );
+    assert_eq!(result.depth, 0);
+    assert!(game
+        .board()
+        .generate_moves()
+        .contains(&result.best_move.unwrap()));
+    assert_eq!(result.pv, [result.best_move.unwrap()]);
+}
+
+#[test]
+fn time_limit() {
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    let limits = Limits {
+        time: Some(TimeLimits::fixed(
+            Duration::from_millis(200),
+            Duration::ZERO,
+        )),
+        ..Limits::default()
+    };
+    let start = Instant::now();
+    let result = search(&game, &limits);
+    assert!(result.best_move.is_some());
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
+
+#[test]
+fn mate_limit() {
+    let game = Game::new(Board::from_fen("k7/8/2K5/8/8/8/8/1R5R w - - 0 1"));
+    let limits = Limits {
+        mate: Some(2),
+        ..Limits::default()
+    };
+    let result = search(&game, &limits);
+    assert_eq!(result.score, MATE - 3);
+    assert!(result.depth <= 4);
+
+    // There is no mate in 0, so a single ply is searched.
+    let limits = Limits {
+        mate: Some(0),
+        ..Limits::default()
+    };
+    assert_eq!(search(&game, &limits).depth, 1);
+}
+
+#[test]
+fn sees_recapture_beyond_horizon() {
+    // A one ply search must still see that the pawn on d5 is defended.
+    let result = search_fen("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", 1);
+    assert_ne!(result.best_move, Some(Move::from_str("d2d5")));
+}
+
+fn search_with(fen: &str, depth: u32, options: SearchOptions) -> SearchResult {
+    search_until(
+        &Game::new(Board::from_fen(fen)),
+        &Limits::to_depth(depth),
+        &options,
+        &TranspositionTable::default(),
+        &Signals::default(),
+    )
+}
+
+#[test]
+fn every_combination_of_search_options_finds_mate() {
+    for bits in 0..16 {
+        let options = SearchOptions {
+            threads: 1,
+            multi_pv: 1,
+            pvs: bits & 1 != 0,
+            null_move: bits & 2 != 0,
+            lmr: bits & 4 != 0,
+            check_extensions: bits & 8 != 0,
+        };
+        let result = search_with("k7/8/2K5/8/8/8/8/1R5R w - - 0 1", 4, options);
+        assert_eq!(result.score, MATE - 3, "{options:?}");
+    }
+}
+
+#[test]
+fn null_move_avoids_zugzwang() {
+    // Only Kg8 wins: black then has to move, and every move lets the h-pawn through.
+    // If black could pass, it would hold the draw, so a search trying null moves with only pawns
+    // left sees no win after Kg8, and plays h6 or Kg7, which draw.
+    let result = search_fen("8/7K/4pk2/6pP/8/8/8/8 w - - 0 1", 8);
+    assert_eq!(result.best_move, Some(Move::from_str("h7g8")));
+    assert!(result.score > 300);
+}
+
+#[test]
+fn check_extensions_see_deeper_mates() {
+    // The king uncovers a check from the queen, and after the forced reply a quiet queen move mates.
+    // That is three plies, found by a two ply search only when the check is extended.
+    let fen = "k7/8/2K5/8/8/8/6Q1/8 w - - 0 1";
+    let extended = search_with(fen, 2, SearchOptions::default());
+    assert_eq!(extended.score, MATE - 3);
+    let not_extended = search_with(
+        fen,
+        2,
+        SearchOptions {
+            check_extensions: false,
+            ..SearchOptions::default()
+        },
+    );
+    assert!(not_extended.score < MATE - 3);
+}
+
+#[test]
+fn multi_pv() {
+    let options = SearchOptions {
+        multi_pv: 3,
+        ..SearchOptions::default()
+    };
+    let result = search_with("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 4, options);
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(result.lines[0].pv, result.pv);
+    assert_eq!(result.best_move, Some(Move::from_str("d1d5")));
+    for pair in result.lines.windows(2) {
+        assert!(pair[0].score >= pair[1].score);
+        assert_ne!(pair[0].pv[0], pair[1].pv[0]);
+    }
+    assert_ne!(result.lines[0].pv[0], result.lines[2].pv[0]);
+    // Anything but taking the queen leaves white a queen down.
+    assert!(result.lines[1].score < 0);
+}
+
+#[test]
+fn multi_pv_with_few_legal_moves() {
+    // The rook leaves the king in the corner a single move.
+    let options = SearchOptions {
+        multi_pv: 5,
+        ..SearchOptions::default()
+    };
+    let result = search_with("k7/8/8/8/8/8/8/1R5K b - - 0 1", 3, options);
+    assert_eq!(result.lines.len(), 1);
+    assert_eq!(result.best_move, Some(Move::from_str("a8a7")));
+}
+
+#[test]
+fn search_moves() {
+    // Taking the queen is best, but not among the moves to search.
+    let game = Game::new(Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1"));
+    let limits = Limits {
+        depth: Some(3),
+        search_moves: vec![Move::from_str("e1e2"), Move::from_str("d1d2")],
+        ..Limits::default()
+    };
+    let result = search(&game, &limits);
+    assert!(limits.search_moves.contains(&result.best_move.unwrap()));
+    assert!(result.score < 0);
+}
+
+#[test]
+fn single_thread_is_deterministic() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let options = SearchOptions {
+        threads: 1,
+        ..SearchOptions::default()
+    };
+    let first = search_with(fen, 5, options);
+    let second = search_with(fen, 5, options);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn several_threads() {
+    let options = SearchOptions {
+        threads: 4,
+        ..SearchOptions::default()
+    };
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let result = search_with(fen, 5, options);
+    assert!(result.depth >= 5);
+    let mut board = Board::from_fen(fen);
+    for m in result.pv {
+        assert!(board.generate_moves().contains(&m), "{m}");
+        board.apply(m);
+    }
+
+    let result = search_with("k7/8/2K5/8/8/8/8/1R5R w - - 0 1", 4, options);
+    assert_eq!(result.score, MATE - 3);
+
+    let result = search_with("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 4, options);
+    assert_eq!(result.best_move, Some(Move::from_str("d1d5")));
+}
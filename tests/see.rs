@@ -0,0 +1,89 @@
+use justok::bitboard::square_bb;
+use justok::board::Board;
+use justok::{square_from_algebraic, Color, Move};
+
+fn see(fen: &str, m: &str) -> i32 {
+    Board::from_fen(fen).see(Move::from_str(m))
+}
+
+fn squares_bb(squares: &[&str]) -> u64 {
+    squares
+        .iter()
+        .map(|s| square_bb(square_from_algebraic(s)))
+        .fold(0, |bb, s| bb | s)
+}
+
+#[test]
+fn attackers_to() {
+    let board = Board::from_fen("4k3/8/2n5/3p4/4P3/5B2/8/Q3K1R1 w - - 0 1");
+    let d5 = square_from_algebraic("d5");
+    // The bishop on f3 is blocked by the pawn.
+    assert_eq!(board.attackers_to(d5, Color::White), squares_bb(&["e4"]));
+    assert_eq!(board.attackers_to(d5, Color::Black), 0);
+    let e4 = square_from_algebraic("e4");
+    assert_eq!(board.attackers_to(e4, Color::Black), squares_bb(&["d5"]));
+    // The queen on a1 is blocked by the bishop, the rook on g1 sees the whole file.
+    let g8 = square_from_algebraic("g8");
+    assert_eq!(board.attackers_to(g8, Color::White), squares_bb(&["g1"]));
+    let e1 = square_from_algebraic("e1");
+    assert_eq!(
+        board.attackers_to(e1, Color::White),
+        squares_bb(&["a1", "g1"])
+    );
+}
+
+#[test]
+fn undefended() {
+    assert_eq!(
+        see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+        100
+    );
+    assert_eq!(see("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", "d1d5"), 900);
+}
+
+#[test]
+fn defended() {
+    // Queen takes a pawn defended by a pawn.
+    assert_eq!(see("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "d2d5"), -800);
+    // Knight takes a pawn that is defended once, and the pawn recaptures to win a pawn back.
+    assert_eq!(see("4k3/8/2p5/3p4/4P3/2N5/8/4K3 w - - 0 1", "c3d5"), -100);
+    assert_eq!(see("4k3/8/2p5/3p4/4P3/2N5/8/4K3 w - - 0 1", "e4d5"), 100);
+    // Trading knights.
+    assert_eq!(see("4k3/8/5p2/4n3/8/3N4/8/4K3 w - - 0 1", "d3e5"), 0);
+}
+
+#[test]
+fn x_rays() {
+    // The second rook joins in once the first has captured.
+    assert_eq!(see("4k3/4r3/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5"), 100);
+    // Black's second rook defends as well.
+    assert_eq!(see("4k3/4r3/4r3/4p3/8/8/4R3/4K3 w - - 0 1", "e2e5"), -400);
+    // A queen behind a bishop.
+    assert_eq!(see("4k3/8/5n2/4p3/3B4/2Q5/8/4K3 w - - 0 1", "d4e5"), 100);
+    assert_eq!(
+        see(
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "d3e5"
+        ),
+        -200
+    );
+}
+
+#[test]
+fn special_moves() {
+    assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+    assert_eq!(see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 0);
+    // Promoting on a square the rook guards gives the queen away.
+    assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), -100);
+    assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q"), 1300);
+    // Moving to a square no piece attacks neither wins nor loses anything.
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/2B1K3 w - - 0 1", "c1e3"), 0);
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/2B1K3 w - - 0 1", "c1f4"), 0);
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/2B1K3 w - - 0 1", "c1g5"), 0);
+    assert_eq!(see("4k3/8/8/8/2p5/8/8/2B1K3 w - - 0 1", "c1d2"), 0);
+    assert_eq!(see("4k3/8/8/8/4p3/8/8/2B1K3 w - - 0 1", "c1d2"), 0);
+    // Moving to an attacked square without capturing loses the piece, less what the recapture wins back.
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/4KB2 w - - 0 1", "f1c4"), -300);
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/4KB2 w - - 0 1", "f1e2"), 0);
+    assert_eq!(see("4k3/8/8/8/8/4p3/8/2B1K3 w - - 0 1", "c1d2"), -200);
+}
@@ -0,0 +1,126 @@
+use justok::board::Board;
+use justok::game::Game;
+use justok::search::{search_until, Limits, SearchOptions, Signals, MATE};
+use justok::tt::{Bound, Entry, TranspositionTable};
+use justok::Move;
+
+const HASH: u64 = 0x0123_4567_89AB_CDEF;
+
+#[test]
+fn store_and_probe() {
+    let tt = TranspositionTable::new(1);
+    assert_eq!(tt.probe(HASH, 0), None);
+
+    let m = Move::from_str("a7a8q");
+    tt.store(HASH, 3, 7, Bound::Lower, -250, Some(m));
+    assert_eq!(
+        tt.probe(HASH, 5),
+        Some(Entry {
+            depth: 7,
+            bound: Bound::Lower,
+            score: -250,
+            best_move: Some(m),
+        })
+    );
+    // Same bucket, but not the same position.
+    assert_eq!(tt.probe(HASH ^ 1, 3), None);
+
+    tt.clear();
+    assert_eq!(tt.probe(HASH, 3), None);
+}
+
+#[test]
+fn mate_scores_are_relative_to_position() {
+    let tt = TranspositionTable::new(1);
+    // Mate in 3 plies from a position 4 plies from the root.
+    tt.store(HASH, 4, 3, Bound::Exact, MATE - 7, None);
+    assert_eq!(tt.probe(HASH, 2).unwrap().score, MATE - 5);
+    tt.store(HASH, 4, 3, Bound::Exact, -MATE + 7, None);
+    assert_eq!(tt.probe(HASH, 6).unwrap().score, -MATE + 9);
+}
+
+#[test]
+fn keep_move_without_new_one() {
+    let tt = TranspositionTable::new(1);
+    let m = Move::from_str("e2e4");
+    tt.store(HASH, 0, 2, Bound::Exact, 30, Some(m));
+    tt.store(HASH, 0, 3, Bound::Upper, 10, None);
+    let entry = tt.probe(HASH, 0).unwrap();
+    assert_eq!(entry.depth, 3);
+    assert_eq!(entry.best_move, Some(m));
+}
+
+#[test]
+fn replace_shallow_and_old_entries() {
+    // A table with a single bucket, so every position competes for the same slots.
+    let tt = TranspositionTable::new(0);
+    for i in 0..4 {
+        tt.store(i, 0, 10 + i as u32, Bound::Exact, 0, None);
+    }
+    tt.store(4, 0, 1, Bound::Exact, 0, None);
+    assert_eq!(tt.probe(0, 0), None, "shallowest entry is replaced");
+    assert!((1..5).all(|i| tt.probe(i, 0).is_some()));
+
+    // Deep entries from older searches make way for new ones.
+    tt.new_search();
+    tt.new_search();
+    for i in 5..9 {
+        tt.store(i, 0, 1, Bound::Exact, 0, None);
+    }
+    assert!((1..5).all(|i| tt.probe(i, 0).is_none()));
+    assert!((5..9).all(|i| tt.probe(i, 0).is_some()));
+}
+
+#[test]
+fn hashfull() {
+    let tt = TranspositionTable::new(1);
+    assert_eq!(tt.hashfull(), 0);
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    search_until(
+        &game,
+        &Limits::to_depth(4),
+        &SearchOptions::default(),
+        &tt,
+        &Signals::default(),
+    );
+    assert!(tt.hashfull() > 0);
+    tt.new_search();
+    assert_eq!(
+        tt.hashfull(),
+        0,
+        "only entries from the current search count"
+    );
+}
+
+#[test]
+fn table_saves_work() {
+    let game = Game::new(Board::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ));
+    let tt = TranspositionTable::new(1);
+    let signals = Signals::default();
+    let first = search_until(
+        &game,
+        &Limits::to_depth(4),
+        &SearchOptions::default(),
+        &tt,
+        &signals,
+    );
+    let second = search_until(
+        &game,
+        &Limits::to_depth(4),
+        &SearchOptions::default(),
+        &tt,
+        &signals,
+    );
+    assert!(second.nodes < first.nodes / 2);
+    assert_eq!(first.best_move, second.best_move);
+}
+
+#[test]
+fn too_large_table_is_an_error() {
+    assert!(TranspositionTable::try_new(usize::MAX).is_err());
+    assert!(TranspositionTable::try_new(1).is_ok());
+}
@@ -0,0 +1,663 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use justok::board::Board;
+use justok::search::{Limits, SearchOptions};
+use justok::search::{MATE, MAX_DEPTH};
+use justok::time::{TimeLimits, DEFAULT_MOVE_OVERHEAD};
+use justok::uci::{format_score, GoParams, Options, OPTIONS};
+use justok::{Color, Move};
+
+fn parse(command: &str) -> Result<GoParams, String> {
+    GoParams::parse(command.split_whitespace())
+}
+
+#[test]
+fn parse_go() {
+    let params = parse("wtime 10000 btime -20 winc 100 binc 0 movestogo 12").unwrap();
+    assert_eq!(
+        params,
+        GoParams {
+            wtime: Some(Duration::from_millis(10000)),
+            btime: Some(Duration::ZERO),
+            winc: Some(Duration::from_millis(100)),
+            binc: Some(Duration::ZERO),
+            movestogo: Some(12),
+            ..GoParams::default()
+        }
+    );
+
+    let params = parse("depth 6 nodes 5000 mate 3 movetime 250 infinite").unwrap();
+    assert_eq!(
+        params,
+        GoParams {
+            depth: Some(6),
+            nodes: Some(5000),
+            mate: Some(3),
+            movetime: Some(Duration::from_millis(250)),
+            infinite: true,
+            ..GoParams::default()
+        }
+    );
+
+    assert_eq!(parse("").unwrap(), GoParams::default());
+}
+
+#[test]
+fn parse_searchmoves() {
+    let params = parse("searchmoves e2e4 d2d4 depth 5").unwrap();
+    assert_eq!(params.searchmoves, ["e2e4", "d2d4"]);
+    assert_eq!(params.depth, Some(5));
+
+    let params = parse("infinite searchmoves a7a8q").unwrap();
+    assert_eq!(params.searchmoves, ["a7a8q"]);
+    assert!(params.infinite);
+
+    let params = parse("searchmoves e2e4 ponder wtime 100").unwrap();
+    assert_eq!(params.searchmoves, ["e2e4"]);
+    assert!(params.ponder);
+}
+
+#[test]
+fn search_moves_must_be_legal() {
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let params = parse("searchmoves e2e4 e2e5 xyzw e2e4 g1f3 e7e5 e2 a2a3k").unwrap();
+    let (moves, warnings) = params.search_moves(&board);
+    assert_eq!(moves, [Move::from_str("e2e4"), Move::from_str("g1f3")]);
+    assert_eq!(warnings.len(), 5);
+    assert!(warnings[0].contains("'e2e5'"));
+}
+
+#[test]
+fn parse_perft() {
+    assert_eq!(parse("perft 4").unwrap().perft, Some(4));
+    assert!(parse("perft").is_err());
+}
+
+#[test]
+fn parse_go_errors() {
+    assert!(parse("depth").is_err());
+    assert!(parse("depth x").is_err());
+    assert!(parse("nodes -5").is_err());
+    assert!(parse("wtime 100 sideways").is_err());
+    assert!(parse("mate 0").is_err());
+}
+
+#[test]
+fn mate_is_clamped_to_max_depth() {
+    assert_eq!(parse("mate 4000000000").unwrap().mate, Some(MAX_DEPTH / 2));
+}
+
+#[test]
+fn limits_use_own_clock() {
+    let params = parse("wtime 60000 btime 1000 winc 0 binc 0").unwrap();
+    let white = params
+        .limits(Color::White, DEFAULT_MOVE_OVERHEAD)
+        .time
+        .unwrap();
+    let black = params
+        .limits(Color::Black, DEFAULT_MOVE_OVERHEAD)
+        .time
+        .unwrap();
+    assert!(white.soft > black.soft);
+    assert!(black.hard < Duration::from_millis(1000));
+}
+
+#[test]
+fn limits_without_time() {
+    assert_eq!(
+        parse("depth 4")
+            .unwrap()
+            .limits(Color::White, DEFAULT_MOVE_OVERHEAD),
+        Limits::to_depth(4)
+    );
+    assert_eq!(
+        parse("wtime 1000 infinite")
+            .unwrap()
+            .limits(Color::White, DEFAULT_MOVE_OVERHEAD),
+        Limits::default()
+    );
+    let movetime = parse("wtime 1000 movetime 500")
+        .unwrap()
+        .limits(Color::White, DEFAULT_MOVE_OVERHEAD);
+    assert_eq!(
+        movetime.time,
+        Some(TimeLimits::fixed(
+            Duration::from_millis(500),
+            DEFAULT_MOVE_OVERHEAD
+        ))
+    );
+}
+
+#[test]
+fn time_allotment() {
+    // tc=10+0.1 from the start of a game.
+    let start = TimeLimits::from_clock(
+        Duration::from_secs(10),
+        Duration::from_millis(100),
+        None,
+        DEFAULT_MOVE_OVERHEAD,
+    );
+    assert!(start.soft > Duration::from_millis(200));
+    assert!(start.soft <= start.hard);
+    assert!(start.hard < Duration::from_secs(4));
+
+    // Almost flagging, must still answer in time.
+    let low = TimeLimits::from_clock(
+        Duration::from_millis(30),
+        Duration::ZERO,
+        None,
+        DEFAULT_MOVE_OVERHEAD,
+    );
+    assert!(low.hard < Duration::from_millis(20));
+
+    // The last move before the time control may use more of the clock.
+    let last_move = TimeLimits::from_clock(
+        Duration::from_secs(6),
+        Duration::ZERO,
+        Some(1),
+        DEFAULT_MOVE_OVERHEAD,
+    );
+    assert!(last_move.soft > start.soft);
+}
+
+#[test]
+fn move_overhead_is_reserved() {
+    let params = parse("movetime 1000").unwrap();
+    let limits = params.limits(Color::White, Duration::from_millis(300));
+    assert_eq!(limits.time.unwrap().hard, Duration::from_millis(700));
+}
+
+#[test]
+fn parse_setoption() {
+    let words = "name Move Overhead value 100".split_whitespace();
+    assert_eq!(
+        Options::parse_setoption(words),
+        Ok(("Move Overhead".to_string(), Some("100".to_string())))
+    );
+    let words = "name Clear Hash".split_whitespace();
+    assert_eq!(
+        Options::parse_setoption(words),
+        Ok(("Clear Hash".to_string(), None))
+    );
+    assert!(Options::parse_setoption("Hash value 3".split_whitespace()).is_err());
+}
+
+#[test]
+fn set_options() {
+    let mut options = Options::default();
+    assert_eq!(options.set("hash", Some("64")), Ok("Hash"));
+    assert_eq!(options.set("Threads", Some("4")), Ok("Threads"));
+    assert_eq!(options.set("MultiPV", Some("3")), Ok("MultiPV"));
+    assert_eq!(
+        options.set("move overhead", Some("50")),
+        Ok("Move Overhead")
+    );
+    assert_eq!(options.set("Clear Hash", None), Ok("Clear Hash"));
+    assert_eq!(options.set("ponder", Some("true")), Ok("Ponder"));
+    assert_eq!(
+        options.set("null move pruning", Some("false")),
+        Ok("Null Move Pruning")
+    );
+    assert_eq!(
+        options,
+        Options {
+            hash: 64,
+            move_overhead: Duration::from_millis(50),
+            ponder: true,
+            search: SearchOptions {
+                threads: 4,
+                multi_pv: 3,
+                null_move: false,
+                ..SearchOptions::default()
+            },
+        }
+    );
+}
+
+#[test]
+fn toggle_search_features() {
+    let mut options = Options::default();
+    for name in [
+        "PVS",
+        "Null Move Pruning",
+        "Late Move Reductions",
+        "Check Extensions",
+    ] {
+        assert_eq!(options.set(name, Some("false")), Ok(name));
+    }
+    assert_eq!(
+        options.search,
+        SearchOptions {
+            threads: 1,
+            multi_pv: 1,
+            pvs: false,
+            null_move: false,
+            lmr: false,
+            check_extensions: false,
+        }
+    );
+    assert!(options.set("PVS", Some("off")).is_err());
+    assert_eq!(options.set("PVS", Some("true")), Ok("PVS"));
+    assert!(options.search.pvs);
+}
+
+#[test]
+fn reject_bad_options() {
+    let mut options = Options::default();
+    assert!(options.set("Hash", Some("0")).is_err());
+    assert!(options.set("Hash", Some("65536")).is_err());
+    assert!(options.set("Threads", Some("100000")).is_err());
+    assert!(options.set("MultiPV", Some("many")).is_err());
+    assert!(options.set("Move Overhead", None).is_err());
+    assert!(options.set("Contempt", Some("10")).is_err());
+    assert_eq!(options, Options::default());
+}
+
+#[test]
+fn advertise_options() {
+    let lines: Vec<String> = OPTIONS.iter().map(|o| o.to_string()).collect();
+    assert!(lines.contains(&"option name Hash type spin default 16 min 1 max 4096".to_string()));
+    assert!(lines.contains(&"option name Clear Hash type button".to_string()));
+}
+
+#[test]
+fn format_scores() {
+    assert_eq!(format_score(35), "cp 35");
+    assert_eq!(format_score(-120), "cp -120");
+    assert_eq!(format_score(MATE - 1), "mate 1");
+    assert_eq!(format_score(MATE - 3), "mate 2");
+    assert_eq!(format_score(-MATE + 2), "mate -1");
+    assert_eq!(format_score(-MATE + 4), "mate -2");
+}
+
+/// The engine binary, talked to over its standard input and output.
+struct Engine {
+    process: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    fn start() -> Self {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_justok"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = process.stdin.take().unwrap();
+        let stdout = BufReader::new(process.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                if sender.send(line.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            process,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{command}").unwrap();
+    }
+
+    /// Read lines until one starts with `prefix`, returning the lines read, the last one included.
+    fn read_until(&self, prefix: &str) -> Vec<String> {
+        let mut read = Vec::new();
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap_or_else(|_| panic!("no '{prefix}' from engine, got {read:?}"));
+            let done = line.starts_with(prefix);
+            read.push(line);
+            if done {
+                return read;
+            }
+        }
+    }
+
+    /// Quit and return everything the engine printed that wasn't read yet.
+    fn quit(mut self) -> Vec<String> {
+        self.send("quit");
+        self.process.wait().unwrap();
+        self.lines.iter().collect()
+    }
+}
+
+fn count_best_moves(lines: &[String]) -> usize {
+    lines.iter().filter(|l| l.starts_with("bestmove")).count()
+}
+
+#[test]
+fn isready_during_search() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go infinite");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert_eq!(count_best_moves(&lines), 0);
+
+    engine.send("stop");
+    assert_eq!(count_best_moves(&engine.read_until("bestmove")), 1);
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn infinite_search_waits_for_stop() {
+    let mut engine = Engine::start();
+    // Mate is found at once, but the best move may only be sent after 'stop'.
+    engine.send("position fen k7/8/1K6/8/8/8/8/7R w - - 0 1");
+    engine.send("go infinite");
+    thread::sleep(Duration::from_millis(200));
+    engine.send("isready");
+    assert_eq!(count_best_moves(&engine.read_until("readyok")), 0);
+
+    engine.send("stop");
+    assert_eq!(
+        engine.read_until("bestmove").last().unwrap(),
+        "bestmove h1h8"
+    );
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn stop_after_search_ended() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go depth 2");
+    engine.read_until("bestmove");
+    engine.send("stop");
+    engine.send("ponderhit");
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn unreadable_go_searches_briefly() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go wtime abc");
+    let lines = engine.read_until("bestmove");
+    assert!(lines[0].starts_with("info string"));
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn go_mate_zero() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go mate 0");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert!(lines[0].starts_with("info string"));
+    engine.send("stop");
+    assert_eq!(count_best_moves(&engine.read_until("bestmove")), 1);
+    // The engine survived the search and still answers.
+    engine.send("isready");
+    engine.read_until("readyok");
+    engine.quit();
+}
+
+#[test]
+fn quit_during_search() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go wtime 100000 btime 100000");
+    assert_eq!(count_best_moves(&engine.quit()), 1);
+}
+
+#[test]
+fn setoption() {
+    let mut engine = Engine::start();
+    engine.send("uci");
+    let lines = engine.read_until("uciok");
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with("option name Threads type spin")));
+
+    engine.send("setoption name Hash value 100000000");
+    engine.send("setoption name Move Overhead value 20");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("info string"));
+    engine.quit();
+}
+
+#[test]
+fn report_mate_scores() {
+    let mut engine = Engine::start();
+    engine.send("position fen k7/8/2K5/8/8/8/8/1R5R w - - 0 1");
+    engine.send("go depth 4");
+    let lines = engine.read_until("bestmove");
+    assert!(lines[lines.len() - 2].starts_with("info depth 4 score mate 2 "));
+
+    engine.send("position fen k7/8/2K5/8/8/8/8/1R5R b - - 0 1");
+    engine.send("go depth 4");
+    let lines = engine.read_until("bestmove");
+    assert!(lines[lines.len() - 2].starts_with("info depth 4 score mate -2 "));
+    engine.quit();
+}
+
+#[test]
+fn report_aspiration_failures() {
+    let mut engine = Engine::start();
+    // The score rises at depth 6, out of the window around the score of depth 5.
+    engine.send("position fen r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+    engine.send("go depth 6");
+    let lines = engine.read_until("bestmove");
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with("info depth 6 ") && l.contains(" lowerbound ")));
+    // The final line of every iteration is exact.
+    assert!(!lines[lines.len() - 2].contains("bound"));
+    engine.quit();
+}
+
+#[test]
+fn report_multiple_lines() {
+    let mut engine = Engine::start();
+    engine.send("setoption name MultiPV value 3");
+    engine.send("position startpos");
+    engine.send("go depth 3");
+    let lines = engine.read_until("bestmove");
+    let last: Vec<&String> = lines.iter().rev().skip(1).take(3).rev().collect();
+    for (k, line) in last.iter().enumerate() {
+        assert!(
+            line.starts_with(&format!("info depth 3 multipv {} score cp ", k + 1)),
+            "{line}"
+        );
+    }
+    let first_move = |line: &str| {
+        line.split(" pv ")
+            .nth(1)
+            .unwrap()
+            .split(' ')
+            .next()
+            .unwrap()
+            .to_string()
+    };
+    assert!(lines
+        .last()
+        .unwrap()
+        .starts_with(&format!("bestmove {} ", first_move(last[0]))));
+    assert_ne!(first_move(last[0]), first_move(last[1]));
+    engine.quit();
+}
+
+#[test]
+fn searchmoves() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go depth 3 searchmoves a2a3 e2e5 h2h3");
+    let lines = engine.read_until("bestmove");
+    assert_eq!(
+        lines[0],
+        "info string Ignoring illegal move 'e2e5' in searchmoves"
+    );
+    let best_move = lines.last().unwrap();
+    assert!(best_move.starts_with("bestmove a2a3") || best_move.starts_with("bestmove h2h3"));
+    engine.quit();
+}
+
+#[test]
+fn bestmove_with_ponder_move() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go depth 4");
+    let lines = engine.read_until("bestmove");
+    let pv: Vec<&str> = lines[lines.len() - 2]
+        .split(" pv ")
+        .nth(1)
+        .unwrap()
+        .split(' ')
+        .collect();
+    assert_eq!(
+        lines.last().unwrap(),
+        &format!("bestmove {} ponder {}", pv[0], pv[1])
+    );
+    engine.quit();
+}
+
+#[test]
+fn ponderhit_starts_the_clock() {
+    let mut engine = Engine::start();
+    engine.send("uci");
+    let lines = engine.read_until("uciok");
+    assert!(lines.contains(&"option name Ponder type check default false".to_string()));
+
+    // With this little time the search would be done long before the sleep is over, if it weren't pondering.
+    engine.send("position startpos moves e2e4");
+    engine.send("go ponder wtime 300 btime 300");
+    thread::sleep(Duration::from_millis(500));
+    engine.send("isready");
+    assert_eq!(count_best_moves(&engine.read_until("readyok")), 0);
+
+    engine.send("ponderhit");
+    let start = std::time::Instant::now();
+    engine.read_until("bestmove");
+    assert!(start.elapsed() < Duration::from_millis(1000));
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn stop_while_pondering() {
+    let mut engine = Engine::start();
+    // The search ends at once, but the best move may only be sent once the ponder is over.
+    engine.send("position fen k7/8/1K6/8/8/8/8/7R w - - 0 1");
+    engine.send("go ponder depth 1");
+    thread::sleep(Duration::from_millis(200));
+    engine.send("isready");
+    assert_eq!(count_best_moves(&engine.read_until("readyok")), 0);
+
+    engine.send("stop");
+    assert_eq!(
+        engine.read_until("bestmove").last().unwrap(),
+        "bestmove h1h8"
+    );
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn search_with_several_threads() {
+    let mut engine = Engine::start();
+    engine.send("setoption name Threads value 3");
+    engine.send("position startpos moves e2e4");
+    engine.send("go depth 5");
+    let best_move = engine.read_until("bestmove").pop().unwrap();
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    let m = best_move.split(' ').nth(1).unwrap();
+    assert!(board.generate_moves().contains(&Move::from_str(m)));
+
+    // Stopping stops the helper threads too.
+    engine.send("go infinite");
+    thread::sleep(Duration::from_millis(200));
+    engine.send("stop");
+    assert_eq!(count_best_moves(&engine.read_until("bestmove")), 1);
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn go_perft() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e4");
+    engine.send("go perft 2");
+    let lines = engine.read_until("Nodes/second");
+    assert_eq!(lines.len(), 20 + 4);
+    assert!(lines.contains(&"e7e5: 29".to_string()));
+    assert_eq!(lines[20], "");
+    assert_eq!(lines[21], "Nodes searched: 600");
+    assert!(lines[22].starts_with("Total time (ms) : "));
+    // Perft isn't a search, so there is no best move.
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn go_perft_zero() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go perft 0");
+    let lines = engine.read_until("Nodes/second");
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1], "Nodes searched: 1");
+    engine.quit();
+}
+
+#[test]
+fn position_with_unreadable_moves() {
+    let mut engine = Engine::start();
+    for command in ["position startpos moves e2", "position startpos moves é2e4"] {
+        engine.send("position startpos moves e2e4");
+        engine.send(command);
+        engine.send("board");
+        let lines = engine.read_until("No position");
+        assert!(lines[0].starts_with("info string Could not read move"));
+        // The previous position is dropped too.
+        assert_eq!(lines.len(), 2);
+    }
+    engine.quit();
+}
+
+#[test]
+fn position_with_illegal_moves() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e4");
+    engine.send("position startpos moves e2e5 e7e5");
+    engine.send("go depth 1");
+    let lines = engine.read_until("info string No position");
+    assert_eq!(lines[0], "info string Illegal move 'e2e5'");
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn go_without_position() {
+    let mut engine = Engine::start();
+    engine.send("go depth 1");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert!(lines[0].starts_with("info string No position"));
+    assert_eq!(count_best_moves(&engine.quit()), 0);
+}
+
+#[test]
+fn position_is_repaired_visibly() {
+    let mut engine = Engine::start();
+    engine.send("position fen 4k3/8/8/8/8/8/8/4K3 w KQ e6 0 1");
+    engine.send("board");
+    let lines = engine.read_until("fen:");
+    let repairs: Vec<_> = lines
+        .iter()
+        .filter(|l| l.starts_with("info string Ignoring"))
+        .collect();
+    assert_eq!(repairs.len(), 3);
+    assert_eq!(lines.last().unwrap(), "fen: 4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    engine.quit();
+}
@@ -0,0 +1,121 @@
+use justok::board::{Board, Violation};
+use justok::Color;
+
+#[test]
+fn legal_positions() {
+    let positions = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "4k3/8/8/8/8/8/8/QQQQKQQQ w - - 0 1",
+    ];
+    for fen in positions {
+        assert_eq!(Board::from_fen(fen).validate(), vec![], "{fen}");
+    }
+}
+
+#[test]
+fn king_count() {
+    let board = Board::from_fen("8/8/8/8/8/8/8/K1K5 w - - 0 1");
+    assert_eq!(
+        board.validate(),
+        vec![
+            Violation::KingCount {
+                color: Color::White,
+                count: 2
+            },
+            Violation::KingCount {
+                color: Color::Black,
+                count: 0
+            },
+        ]
+    );
+}
+
+#[test]
+fn pawn_on_back_rank() {
+    let board = Board::from_fen("4k2P/8/8/8/8/8/8/p3K3 w - - 0 1");
+    assert_eq!(
+        board.validate(),
+        vec![
+            Violation::PawnOnBackRank { square: 0 },
+            Violation::PawnOnBackRank { square: 63 },
+        ]
+    );
+}
+
+#[test]
+fn opponent_in_check() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4RK2 w - - 0 1");
+    assert_eq!(board.validate(), vec![Violation::OpponentInCheck]);
+}
+
+#[test]
+fn castling_rights() {
+    let mut board = Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1");
+    assert_eq!(
+        board.validate(),
+        vec![
+            Violation::CastlingRights {
+                color: Color::White,
+                king_side: false
+            },
+            Violation::CastlingRights {
+                color: Color::Black,
+                king_side: true
+            },
+        ]
+    );
+    let violations = board.validate();
+    assert_eq!(board.repair(), violations);
+    assert_eq!(board.validate(), vec![]);
+    assert_eq!(board.to_fen(), "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1");
+}
+
+#[test]
+fn en_pessant() {
+    let cases = [
+        // No pawn in front of the square.
+        "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+        // Wrong side to move.
+        "4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1",
+        // Square the pawn came from is occupied.
+        "4k3/8/8/8/4P3/8/4N3/4K3 b - e3 0 1",
+    ];
+    for fen in cases {
+        let mut board = Board::from_fen(fen);
+        assert_eq!(
+            board.validate(),
+            vec![Violation::EnPessant { square: 20 }],
+            "{fen}"
+        );
+        board.repair();
+        assert_eq!(board.validate(), vec![], "{fen}");
+    }
+}
+
+#[test]
+fn too_many_pieces() {
+    let board = Board::from_fen("4k3/8/8/8/8/PPPPPPPP/PNNNNPPP/4K3 w - - 0 1");
+    assert_eq!(
+        board.validate(),
+        vec![
+            Violation::TooManyPieces {
+                color: Color::White,
+                count: 17
+            },
+            Violation::TooManyPawns {
+                color: Color::White,
+                count: 12
+            },
+        ]
+    );
+
+    let board = Board::from_fen("4k3/8/8/8/8/8/PPPPPPPP/NNN1K3 w - - 0 1");
+    assert_eq!(
+        board.validate(),
+        vec![Violation::TooManyPromotions {
+            color: Color::White
+        }]
+    );
+}
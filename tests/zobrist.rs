@@ -0,0 +1,97 @@
+use justok::board::Board;
+use justok::Move;
+
+/// The incrementally updated hash must match the hash computed from scratch at every node.
+fn assert_incremental_hash(board: &mut Board, depth: usize) {
+    assert_eq!(
+        board.hash(),
+        Board::from_fen(&board.to_fen()).hash(),
+        "{}",
+        board.to_fen()
+    );
+    if depth == 0 {
+        return;
+    }
+    for m in board.generate_moves() {
+        let undo = board.make_move(m);
+        assert_incremental_hash(board, depth - 1);
+        board.unmake_move(m, undo);
+    }
+}
+
+#[test]
+fn incremental_matches_scratch() {
+    let positions = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    for fen in positions {
+        assert_incremental_hash(&mut Board::from_fen(fen), 3);
+    }
+}
+
+#[test]
+fn transpositions() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let play = |moves: &[&str]| {
+        let mut board = Board::from_fen(start);
+        for m in moves {
+            board.apply(Move::from_str(m));
+        }
+        board.hash()
+    };
+    assert_eq!(
+        play(&["g1f3", "g8f6", "b1c3"]),
+        play(&["b1c3", "g8f6", "g1f3"])
+    );
+    // Knights going out and back again is the starting position, even though the clocks differ.
+    assert_eq!(
+        play(&["g1f3", "g8f6", "f3g1", "f6g8"]),
+        Board::from_fen(start).hash()
+    );
+    assert_ne!(play(&["e2e4"]), play(&["e2e3"]));
+}
+
+#[test]
+fn position_features() {
+    let hash = |fen: &str| Board::from_fen(fen).hash();
+    // Side to move.
+    assert_ne!(
+        hash("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+        hash("4k3/8/8/8/8/8/8/4K3 b - - 0 1")
+    );
+    // Castling rights.
+    assert_ne!(
+        hash("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"),
+        hash("r3k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1")
+    );
+    // En pessant square only counts when a pawn can capture.
+    assert_ne!(
+        hash("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+        hash("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1")
+    );
+    assert_eq!(
+        hash("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+        hash("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1")
+    );
+}
+
+#[test]
+fn null_move() {
+    // After e4 black could capture en pessant, but not once white has passed instead.
+    let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+    let mut board = Board::from_fen(fen);
+    let undo = board.make_null_move();
+    assert_eq!(
+        board.hash(),
+        Board::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 4").hash()
+    );
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 4"
+    );
+    board.unmake_null_move(undo);
+    assert_eq!(board, Board::from_fen(fen));
+}